@@ -0,0 +1,97 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// Infer the openapi string format from an example literal.
+pub(crate) fn infer_string_format(value: &str) -> Option<&'static str> {
+    if is_date_time(value) {
+        Some("date-time")
+    } else if is_date(value) {
+        Some("date")
+    } else if is_uuid(value) {
+        Some("uuid")
+    } else if is_email(value) {
+        Some("email")
+    } else if value.parse::<Ipv4Addr>().is_ok() {
+        Some("ipv4")
+    } else if value.contains(':') && value.parse::<Ipv6Addr>().is_ok() {
+        Some("ipv6")
+    } else if is_uri(value) {
+        Some("uri")
+    } else {
+        None
+    }
+}
+
+/// Infer the openapi integer format from an example literal.
+pub(crate) fn infer_integer_format(value: i64) -> Option<&'static str> {
+    if i32::try_from(value).is_err() {
+        Some("int64")
+    } else {
+        None
+    }
+}
+
+fn is_digits(value: &str, len: usize) -> bool {
+    value.len() == len && value.bytes().all(|v| v.is_ascii_digit())
+}
+
+fn is_date(value: &str) -> bool {
+    let parts: Vec<&str> = value.split('-').collect();
+    matches!(parts.as_slice(), [year, month, day]
+        if is_digits(year, 4)
+            && is_digits(month, 2)
+            && is_digits(day, 2)
+            && (1..=12).contains(&month.parse::<u8>().unwrap_or_default())
+            && (1..=31).contains(&day.parse::<u8>().unwrap_or_default()))
+}
+
+/// RFC3339 date-time, e.g. `2018-11-13T20:20:39Z` or `2018-11-13T20:20:39.123+08:00`.
+fn is_date_time(value: &str) -> bool {
+    let (date, time) = match value.split_once(['T', 't']) {
+        Some(v) => v,
+        None => return false,
+    };
+    if !is_date(date) {
+        return false;
+    }
+    let (time, offset) = if let Some(time) = time.strip_suffix(['Z', 'z']) {
+        (time, None)
+    } else {
+        match time.rfind(['+', '-']) {
+            Some(index) => (&time[..index], Some(&time[index + 1..])),
+            None => return false,
+        }
+    };
+    let time = time.split_once('.').map(|(v, _)| v).unwrap_or(time);
+    let is_time = |value: &str, parts: usize| {
+        let items: Vec<&str> = value.split(':').collect();
+        items.len() == parts && items.iter().all(|v| is_digits(v, 2))
+    };
+    is_time(time, 3) && offset.map(|v| is_time(v, 2)).unwrap_or(true)
+}
+
+fn is_uuid(value: &str) -> bool {
+    let parts: Vec<&str> = value.split('-').collect();
+    parts.len() == 5
+        && parts
+            .iter()
+            .zip([8, 4, 4, 4, 12])
+            .all(|(part, len)| part.len() == len && part.bytes().all(|v| v.is_ascii_hexdigit()))
+}
+
+fn is_email(value: &str) -> bool {
+    match value.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !value.contains(char::is_whitespace)
+                && !domain.contains('@')
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+        }
+        None => false,
+    }
+}
+
+fn is_uri(value: &str) -> bool {
+    value.contains("://") && url::Url::parse(value).is_ok()
+}
@@ -1,24 +1,54 @@
+mod format;
+mod naming;
 mod openapi;
+mod options;
+mod status;
+mod transform;
 
-use std::{cell::RefCell, collections::HashSet, convert::TryFrom, fmt::Display, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::{hash_map::RandomState, HashMap, HashSet},
+    convert::TryFrom,
+    fmt::Display,
+    hash::{BuildHasher, Hasher},
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
 use indexmap::IndexMap;
 use jsona::{
     dom::{Key, KeyOrIndex, Keys, Node, Object},
     error::ErrorObject,
-    util::mapper::Mapper,
+    util::mapper::{Mapper, Range},
 };
 pub use jsona_schema::Schema;
-use jsona_schema::SchemaParser;
+use jsona_schema::{OneOrMultiSchemas, OneOrMultiTypes, SchemaError, SchemaParser, SchemaType};
 pub use openapi::*;
+pub use options::*;
+use serde::{de::DeserializeOwned, Deserialize};
 use serde_json::Value;
 
 const ERROR_SOURCE: &str = "openapi";
+const SCHEMA_REF_PREFIX: &str = "#/components/schemas/";
+const PARAMETER_REF_PREFIX: &str = "#/components/parameters/";
+const MAX_GENERIC_DEPTH: usize = 8;
+/// Marks a schema waiting for `parse_deferred`, the value is the marker prefix of the parser
+/// followed by the index of the deferred item.
+const DEFERRED_KEY: &str = "x-jsona-deferred";
 
 #[derive(Clone, Debug)]
 pub struct OpenapiError {
     pub keys: Keys,
     pub message: String,
+    location: Option<Box<FileLocation>>,
+}
+
+/// A location in a jsona file.
+#[derive(Clone, Debug)]
+pub struct FileLocation {
+    pub file: PathBuf,
+    /// The range of keys in the file.
+    pub range: Option<Range>,
 }
 
 impl OpenapiError {
@@ -26,73 +56,211 @@ impl OpenapiError {
         Self {
             keys,
             message: message.to_string(),
+            location: None,
         }
     }
+    /// Where the error occurs, only available when parsing from files.
+    pub fn location(&self) -> Option<&FileLocation> {
+        self.location.as_deref()
+    }
+    /// The file where the error occurs, only available when parsing from files.
+    pub fn file(&self) -> Option<&Path> {
+        self.location.as_ref().map(|v| v.file.as_path())
+    }
+    /// Convert to an error object with the range in `node`, or in [`file`](Self::file) when
+    /// the error occurs in a file.
     pub fn to_error_object(&self, node: &Node, mapper: &Mapper) -> ErrorObject {
         let message = self.message.clone();
-        ErrorObject::new(
-            ERROR_SOURCE,
-            "InvalidOpenapi",
-            message,
-            self.keys.mapper_range(node, mapper),
-        )
+        let range = match self.location.as_ref() {
+            Some(location) => location.range,
+            None => self.keys.mapper_range(node, mapper),
+        };
+        ErrorObject::new(ERROR_SOURCE, "InvalidOpenapi", message, range)
+    }
+    fn with_file(mut self, file: &Path, range: Option<Range>) -> Self {
+        self.location = Some(Box::new(FileLocation {
+            file: file.to_path_buf(),
+            range,
+        }));
+        self
     }
 }
 
 impl Display for OpenapiError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if self.keys.is_empty() {
-            write!(f, "{}", self.message)
+            write!(f, "{}", self.message)?;
         } else {
-            write!(f, "{} at {}", self.message, self.keys)
+            write!(f, "{} at {}", self.message, self.keys)?;
         }
+        if let Some(location) = self.location.as_ref() {
+            write!(f, " in {}", location.file.display())?;
+        }
+        Ok(())
     }
 }
 
 type OpenapiResult<T> = std::result::Result<T, OpenapiError>;
 
+/// The openapi with warnings, or the errors.
+type ParseResult = Result<(Openapi, Vec<OpenapiError>), Vec<OpenapiError>>;
+
 impl TryFrom<&Node> for Openapi {
     type Error = Vec<OpenapiError>;
 
     fn try_from(value: &Node) -> Result<Self, Self::Error> {
-        OpenapiParser::parse(value)
+        OpenapiParser::parse(value, &OpenapiOptions::default()).map(|(openapi, _)| openapi)
+    }
+}
+
+impl Openapi {
+    /// Parse openapi from jsona document with custom options.
+    pub fn parse_with_options(
+        node: &Node,
+        options: &OpenapiOptions,
+    ) -> Result<Self, Vec<OpenapiError>> {
+        OpenapiParser::parse(node, options).map(|(openapi, _)| openapi)
+    }
+
+    /// Parse openapi from jsona document with custom options, also returning the warnings
+    /// which do not prevent generating the document.
+    pub fn parse_with_warnings(
+        node: &Node,
+        options: &OpenapiOptions,
+    ) -> Result<(Self, Vec<OpenapiError>), Vec<OpenapiError>> {
+        OpenapiParser::parse(node, options)
+    }
+
+    /// Parse openapi from jsona file, `@include` paths are resolved against the directory
+    /// of the including file.
+    pub fn parse_file(
+        path: impl AsRef<Path>,
+        options: &OpenapiOptions,
+    ) -> Result<Self, Vec<OpenapiError>> {
+        OpenapiParser::parse_file(path.as_ref(), options).map(|(openapi, _)| openapi)
+    }
+
+    /// Parse openapi from jsona file, also returning the warnings.
+    pub fn parse_file_with_warnings(
+        path: impl AsRef<Path>,
+        options: &OpenapiOptions,
+    ) -> Result<(Self, Vec<OpenapiError>), Vec<OpenapiError>> {
+        OpenapiParser::parse_file(path.as_ref(), options)
     }
 }
 
 struct OpenapiParser {
     openapi: Openapi,
+    options: OpenapiOptions,
     routes: HashSet<String>,
+    operation_ids: HashMap<String, Keys>,
+    endpoints: HashMap<String, Vec<(String, MethodKind)>>,
+    links: Vec<PendingLink>,
+    pathnames: HashMap<String, String>,
     errors: Vec<OpenapiError>,
+    warnings: Vec<OpenapiError>,
     defs: Rc<RefCell<IndexMap<String, Schema>>>,
+    def_nodes: HashMap<String, Node>,
+    dir: PathBuf,
+    root_dir: PathBuf,
+    files: Vec<PathBuf>,
+    external_refs: IndexMap<String, ExternalRef>,
+    external_documents: HashMap<PathBuf, Rc<Value>>,
+    imported_refs: HashMap<(PathBuf, String, RefKind), String>,
+    templates: HashMap<String, Template>,
+    template_params: HashSet<String>,
+    template_refs: HashSet<String>,
+    generic_refs: IndexMap<String, GenericRef>,
+    deferred: Vec<Deferred>,
+    /// Random per parser so that a `x-jsona-deferred` written by users is never taken as a marker.
+    deferred_marker: String,
 }
 
 impl OpenapiParser {
-    fn parse(node: &Node) -> Result<Openapi, Vec<OpenapiError>> {
+    fn new(node: &Node, options: &OpenapiOptions) -> Self {
         let mut errors: Vec<OpenapiError> = vec![];
-        let routes: HashSet<String> = HashSet::default();
         let mut openapi = Self::parse_openapi(&mut errors, node);
         let schemas = get_components_mut(&mut openapi)
             .schemas
             .take()
             .unwrap_or_default();
-        let mut parser = OpenapiParser {
+        OpenapiParser {
             openapi,
-            routes,
+            options: options.clone(),
+            routes: HashSet::default(),
+            operation_ids: HashMap::default(),
+            endpoints: HashMap::default(),
+            links: vec![],
+            pathnames: HashMap::default(),
             errors,
+            warnings: vec![],
             defs: Rc::new(RefCell::new(schemas)),
-        };
+            def_nodes: HashMap::default(),
+            dir: options.base_dir.clone().unwrap_or_default(),
+            root_dir: options.base_dir.clone().unwrap_or_default(),
+            files: vec![],
+            external_refs: IndexMap::default(),
+            external_documents: HashMap::default(),
+            imported_refs: HashMap::default(),
+            templates: HashMap::default(),
+            template_params: HashSet::default(),
+            template_refs: HashSet::default(),
+            generic_refs: IndexMap::default(),
+            deferred: vec![],
+            deferred_marker: format!("{:x}:", RandomState::new().build_hasher().finish()),
+        }
+    }
+
+    fn parse(node: &Node, options: &OpenapiOptions) -> ParseResult {
+        let mut parser = Self::new(node, options);
         parser.parse_paths(node);
+        parser.finish()
+    }
+
+    fn parse_file(file: &Path, options: &OpenapiOptions) -> ParseResult {
+        let source = std::fs::read_to_string(file).map_err(|error| {
+            vec![
+                OpenapiError::new(Keys::default(), format!("failed to read file, {error}"))
+                    .with_file(file, None),
+            ]
+        })?;
+        let (node, mapper) = load_file(file, &source)?;
+        let mut parser = Self::new(&node, options);
+        parser.dir = file.parent().map(|v| v.to_path_buf()).unwrap_or_default();
+        parser.root_dir = parser.dir.clone();
+        parser.files.push(canonicalize(file));
+        parser.parse_paths(&node);
+        parser.attach_file(Checkpoint::default(), file, &node, &mapper);
+        parser.finish()
+    }
+
+    fn finish(mut self) -> ParseResult {
+        self.parse_links();
+        self.parse_generics();
+        self.parse_external_refs();
+        self.parse_deferred();
         let OpenapiParser {
             mut openapi,
+            options,
             errors,
+            warnings,
             defs,
             ..
-        } = parser;
+        } = self;
         if errors.is_empty() {
             if !defs.borrow().is_empty() {
                 get_components_mut(&mut openapi).schemas = Some(defs.take());
             }
-            Ok(openapi)
+            if options.split_read_write {
+                openapi.split_read_write_schemas();
+            }
+            if options.name_inline_schemas {
+                openapi.name_inline_schemas();
+            }
+            if options.dedupe_schemas {
+                openapi.hoist_schemas();
+            }
+            Ok((openapi, warnings))
         } else {
             Err(errors)
         }
@@ -146,38 +314,530 @@ impl OpenapiParser {
     }
 
     fn parse_paths(&mut self, node: &Node) {
+        if node.is_object() {
+            self.collect(Declaration::Defs, &Keys::default(), node, &Group::default());
+            self.collect(
+                Declaration::Paths,
+                &Keys::default(),
+                node,
+                &Group::default(),
+            );
+            self.parse_entries(&Keys::default(), node, &Group::default());
+        } else {
+            self.errors
+                .push(OpenapiError::new(Keys::default(), "must be object"))
+        }
+    }
+
+    /// Parse the entries of the root object, an included file or a group body.
+    ///
+    /// In a group body `req` and `res` are the parameters and responses shared by the group,
+    /// they are parsed by `parse_group` instead of as endpoints.
+    fn parse_entries(&mut self, keys: &Keys, node: &Node, group: &Group) {
+        let nested = !keys.is_empty();
+        self.parse_includes(keys, node, group);
         if let Some(object) = node.as_object() {
             for (key, value) in object.value().read().iter() {
-                if let Err(error) = self.parse_endpoint(key, value) {
+                let keys = keys.join(key.clone());
+                let result = if exist_annotation(value, "@defs") || exist_annotation(value, "@path")
+                {
+                    Ok(())
+                } else if exist_annotation(value, "@group") {
+                    self.parse_group(&keys, value, group)
+                } else if exist_annotation(value, "@webhook") {
+                    if nested {
+                        Err(OpenapiError::new(
+                            keys.join(KeyOrIndex::annotation("@webhook")),
+                            "is not supported in group",
+                        ))
+                    } else {
+                        self.parse_webhook(&keys, key, value)
+                    }
+                } else if nested && matches!(key.value(), "req" | "res") {
+                    Ok(())
+                } else {
+                    self.parse_endpoint(&keys, key, value, group)
+                };
+                if let Err(error) = result {
                     self.errors.push(error);
                 }
             }
-        } else {
-            self.errors
-                .push(OpenapiError::new(Keys::default(), "must be object"))
         }
     }
 
-    fn parse_endpoint(&mut self, key: &Key, value: &Node) -> OpenapiResult<()> {
-        let operation_id = key.value();
-        let keys = Keys::single(key.clone());
+    /// Parse the `@defs` or `@path` entries of the root object, included files and group bodies
+    /// before any endpoint, so endpoints see them regardless of the order and of the files.
+    fn collect(&mut self, declaration: Declaration, keys: &Keys, node: &Node, group: &Group) {
+        let (paths, _) = include_paths(keys, node);
+        for path in paths {
+            self.collect_included(declaration, &path, group);
+        }
+        if let Some(object) = node.as_object() {
+            for (key, value) in object.value().read().iter() {
+                let keys = keys.join(key.clone());
+                let result = if exist_annotation(value, "@group") {
+                    if let Some(group) = path_group(&keys, value, group) {
+                        self.collect(declaration, &keys, value, &group);
+                    }
+                    Ok(())
+                } else if exist_annotation(value, "@defs") {
+                    match declaration {
+                        Declaration::Defs => self.parse_defs(&keys, value),
+                        Declaration::Paths => Ok(()),
+                    }
+                } else if exist_annotation(value, "@path") {
+                    match declaration {
+                        Declaration::Defs => Ok(()),
+                        Declaration::Paths => self.parse_path(&keys, value, group),
+                    }
+                } else {
+                    Ok(())
+                };
+                if let Err(error) = result {
+                    self.errors.push(error);
+                }
+            }
+        }
+    }
+
+    /// Errors of unreadable or circular includes are reported by `parse_include`.
+    fn collect_included(&mut self, declaration: Declaration, path: &str, group: &Group) {
+        let file = self.dir.join(path);
+        let canonical_file = canonicalize(&file);
+        if self.files.contains(&canonical_file) {
+            return;
+        }
+        let (node, mapper) = match std::fs::read_to_string(&file)
+            .ok()
+            .and_then(|source| load_file(&file, &source).ok())
+        {
+            Some(v) => v,
+            None => return,
+        };
+        let checkpoint = self.checkpoint();
+        let parent_dir = file.parent().map(|v| v.to_path_buf()).unwrap_or_default();
+        let dir = std::mem::replace(&mut self.dir, parent_dir);
+        self.files.push(canonical_file);
+        self.collect(declaration, &Keys::default(), &node, group);
+        self.files.pop();
+        self.dir = dir;
+        self.attach_file(checkpoint, &file, &node, &mapper);
+    }
+
+    fn parse_includes(&mut self, keys: &Keys, node: &Node, group: &Group) {
+        let (paths, errors) = include_paths(keys, node);
+        self.errors.extend(errors);
+        let keys = match node.get_as_string("@include") {
+            Some((key, _)) => keys.join(key),
+            None => return,
+        };
+        for path in paths {
+            if let Err(errors) = self.parse_include(&keys, &path, group) {
+                self.errors.extend(errors);
+            }
+        }
+    }
+
+    fn parse_include(
+        &mut self,
+        keys: &Keys,
+        path: &str,
+        group: &Group,
+    ) -> Result<(), Vec<OpenapiError>> {
+        let file = self.dir.join(path);
+        let canonical_file = canonicalize(&file);
+        if self.files.contains(&canonical_file) {
+            return Err(vec![OpenapiError::new(
+                keys.clone(),
+                format!("circular include {path}"),
+            )]);
+        }
+        let source = std::fs::read_to_string(&file).map_err(|error| {
+            vec![OpenapiError::new(
+                keys.clone(),
+                format!("failed to read {path}, {error}"),
+            )]
+        })?;
+        let (node, mapper) = load_file(&file, &source)?;
+        if !node.is_object() {
+            return Err(vec![
+                OpenapiError::new(Keys::default(), "must be object").with_file(&file, None)
+            ]);
+        }
+        let checkpoint = self.checkpoint();
+        let parent_dir = file.parent().map(|v| v.to_path_buf()).unwrap_or_default();
+        let dir = std::mem::replace(&mut self.dir, parent_dir);
+        self.files.push(canonical_file);
+        self.parse_entries(&Keys::default(), &node, group);
+        self.files.pop();
+        self.dir = dir;
+        self.attach_file(checkpoint, &file, &node, &mapper);
+        Ok(())
+    }
+
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            errors: self.errors.len(),
+            warnings: self.warnings.len(),
+            links: self.links.len(),
+            external_refs: self.external_refs.len(),
+            generic_refs: self.generic_refs.len(),
+            deferred: self.deferred.len(),
+        }
+    }
+
+    /// Mark errors, warnings, links, external and generic refs and deferred items produced since
+    /// the checkpoint as coming from the file.
+    fn attach_file(&mut self, checkpoint: Checkpoint, file: &Path, node: &Node, mapper: &Mapper) {
+        for error in self.errors[checkpoint.errors..]
+            .iter_mut()
+            .chain(self.warnings[checkpoint.warnings..].iter_mut())
+            .filter(|v| v.location.is_none())
+        {
+            error.location = Some(Box::new(FileLocation {
+                file: file.to_path_buf(),
+                range: error.keys.mapper_range(node, mapper),
+            }));
+        }
+        for link in self.links[checkpoint.links..]
+            .iter_mut()
+            .filter(|v| v.file_location.is_none())
+        {
+            link.file_location = Some(FileLocation {
+                file: file.to_path_buf(),
+                range: link.keys.mapper_range(node, mapper),
+            });
+        }
+        for external_ref in self
+            .external_refs
+            .values_mut()
+            .skip(checkpoint.external_refs)
+            .filter(|v| v.file_location.is_none())
+        {
+            external_ref.file_location = Some(FileLocation {
+                file: file.to_path_buf(),
+                range: external_ref.keys.mapper_range(node, mapper),
+            });
+        }
+        for generic_ref in self
+            .generic_refs
+            .values_mut()
+            .skip(checkpoint.generic_refs)
+            .filter(|v| v.file_location.is_none())
+        {
+            generic_ref.file_location = Some(FileLocation {
+                file: file.to_path_buf(),
+                range: generic_ref.keys.mapper_range(node, mapper),
+            });
+        }
+        for deferred in self.deferred[checkpoint.deferred..]
+            .iter_mut()
+            .filter(|v| v.file_location.is_none())
+        {
+            deferred.file_location = Some(FileLocation {
+                file: file.to_path_buf(),
+                range: deferred.keys.mapper_range(node, mapper),
+            });
+        }
+    }
+
+    fn parse_defs(&mut self, keys: &Keys, value: &Node) -> OpenapiResult<()> {
+        let object = value
+            .as_object()
+            .ok_or_else(|| OpenapiError::new(keys.clone(), "must be object"))?;
+        for (key, value) in object.value().read().iter() {
+            let keys = keys.join(key.clone());
+            let mut schema = self.parse_schema(&keys, value)?;
+            if !exist_annotation(value, "@def") {
+                add_enum_varnames(&mut schema);
+                let mut defs = self.defs.borrow_mut();
+                if defs.contains_key(key.value()) {
+                    return Err(OpenapiError::new(keys, "is conflict"));
+                }
+                defs.insert(key.value().to_string(), schema);
+                self.def_nodes
+                    .insert(key.value().to_string(), value.clone());
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_group(&mut self, keys: &Keys, value: &Node, parent: &Group) -> OpenapiResult<()> {
         if !value.is_object() {
-            return Err(OpenapiError::new(keys, "must be object"));
-        }
-        let mut operation = self.parse_endpoint_annotation(&keys, value)?;
-        operation.operation_id = Some(operation_id.into());
-        let (method, path_parts) = self.parse_route(&keys, value)?;
-        let pathname = self.parse_req(&mut operation, &keys, value, &path_parts)?;
-        self.parse_res(&mut operation, &keys, value)?;
-        let path_item = self
-            .openapi
-            .paths
-            .entry(pathname)
-            .or_insert(Default::default());
+            return Err(OpenapiError::new(keys.clone(), "must be object"));
+        }
+        let options: GroupOptions = parse_object_annotation(keys, value, "@group")?;
+        if let Some(security) = options.security.as_ref() {
+            self.check_security(&keys.join(KeyOrIndex::annotation("@group")), security)?;
+        }
+        let prefix = options.prefix.unwrap_or_default();
+        let path_parts: Vec<String> = prefix.split('/').map(|v| v.to_string()).collect();
+        let mut operation = Operation::default();
+        let pathname = self.parse_req(&mut operation, keys, value, &path_parts, false)?;
+        let mut status_keys = IndexMap::default();
+        if value.get_as_object("res").is_some() {
+            let links_start = self.links.len();
+            status_keys = self.parse_res(&mut operation, keys, value)?;
+            if let Some(link) = self.links.drain(links_start..).next() {
+                return Err(OpenapiError::new(link.keys, "is not supported in group"));
+            }
+        }
+        let mut parameters = parent.parameters.clone();
+        parameters.extend(operation.parameters.unwrap_or_default());
+        let mut responses = parent.responses.clone();
+        responses.extend(operation.responses);
+        self.check_default_response(&responses, &status_keys);
+        let group = Group {
+            prefix: format!("{}{}", parent.prefix, prefix),
+            pathname: format!("{}{}", parent.pathname, pathname),
+            tags: options.tags.or_else(|| parent.tags.clone()),
+            security: options.security.or_else(|| parent.security.clone()),
+            parameters,
+            responses,
+        };
+        self.parse_entries(keys, value, &group);
+        Ok(())
+    }
+
+    fn parse_path(&mut self, keys: &Keys, value: &Node, group: &Group) -> OpenapiResult<()> {
+        if !value.is_object() {
+            return Err(OpenapiError::new(keys.clone(), "must be object"));
+        }
+        let options: PathOptions = parse_object_annotation(keys, value, "@path")?;
+        let path = match value.get_as_string("route") {
+            Some((_, Some(value))) => value.value().trim().to_string(),
+            Some((key, None)) => return Err(OpenapiError::new(keys.join(key), "must be string")),
+            None => return Err(OpenapiError::new(keys.clone(), "miss route")),
+        };
+        let path_parts: Vec<String> = path.split('/').map(|v| v.to_string()).collect();
+        let route_path = format!("{}{}", group.prefix, path);
+        if self.pathnames.contains_key(&route_path) {
+            return Err(OpenapiError::new(
+                keys.join(KeyOrIndex::property("route")),
+                "is conflict",
+            ));
+        }
+        let mut operation = Operation::default();
+        let pathname = self.parse_req(&mut operation, keys, value, &path_parts, false)?;
+        let pathname = format!("{}{}", group.pathname, pathname);
+        let path_item = self.openapi.paths.entry(pathname.clone()).or_default();
+        path_item.summary = options.summary;
+        path_item.description = options.description;
+        path_item.servers = options.servers;
+        path_item.parameters = operation.parameters;
+        path_item.extensions = parse_extension_annotation(keys, value)?;
+        self.pathnames.insert(route_path, pathname);
+        Ok(())
+    }
+
+    fn parse_endpoint(
+        &mut self,
+        keys: &Keys,
+        key: &Key,
+        value: &Node,
+        group: &Group,
+    ) -> OpenapiResult<()> {
+        if !value.is_object() {
+            return Err(OpenapiError::new(keys.clone(), "must be object"));
+        }
+        let mut operation = self.parse_endpoint_annotation(keys, value)?;
+        if let Some(security) = operation.security.as_ref() {
+            self.check_security(&keys.join(KeyOrIndex::annotation("@endpoint")), security)?;
+        }
+        self.parse_security(&mut operation, keys, value)?;
+        let (method, path_parts) = self.parse_route(keys, value, group)?;
+        let route_path = format!("{}{}", group.prefix, path_parts.join("/"));
+        let declared_pathname = self.pathnames.get(&route_path).cloned();
+        let pathname = self.parse_req(
+            &mut operation,
+            keys,
+            value,
+            &path_parts,
+            declared_pathname.is_some(),
+        )?;
+        let mut pathname = format!("{}{}", group.pathname, pathname);
+        if let Some(declared_pathname) = declared_pathname {
+            if pathname.contains("{}") {
+                pathname = declared_pathname;
+            } else if pathname != declared_pathname {
+                return Err(OpenapiError::new(
+                    keys.clone(),
+                    format!("params does not match path {declared_pathname}"),
+                ));
+            }
+        }
+        let links_start = self.links.len();
+        let status_keys = self.parse_res(&mut operation, keys, value)?;
+        for link in self.links[links_start..].iter_mut() {
+            link.location = Some((pathname.clone(), method));
+        }
+        self.parse_callbacks(&mut operation, keys, value)?;
+        group.apply(&mut operation);
+        self.check_default_response(&operation.responses, &status_keys);
+        let operation_id = match operation.operation_id.take() {
+            Some(v) => v,
+            None => self.derive_operation_id(key.value(), &method, &pathname, &operation),
+        };
+        if let Some(other_keys) = self.operation_ids.get(&operation_id) {
+            return Err(OpenapiError::new(
+                keys.clone(),
+                format!("operationId {operation_id} is conflict with {other_keys}"),
+            ));
+        }
+        self.operation_ids
+            .insert(operation_id.clone(), keys.clone());
+        operation.operation_id = Some(operation_id);
+        let path_item = self.openapi.paths.entry(pathname.clone()).or_default();
         method.add_operation(path_item, operation);
+        self.endpoints
+            .entry(key.value().to_string())
+            .or_default()
+            .push((pathname, method));
+        Ok(())
+    }
+
+    fn parse_callbacks(
+        &mut self,
+        operation: &mut Operation,
+        keys: &Keys,
+        value: &Node,
+    ) -> OpenapiResult<()> {
+        let object = match value.as_object() {
+            Some(v) => v,
+            None => return Ok(()),
+        };
+        for (key, value) in object.value().read().iter() {
+            if !exist_annotation(value, "@callback") {
+                continue;
+            }
+            let keys = keys.join(key.clone());
+            let expression = parse_string_annotation(&keys, value, "@callback")?
+                .ok_or_else(|| OpenapiError::new(keys.clone(), "must be string"))?;
+            let (method, callback_operation) = self.parse_detached_operation(&keys, value)?;
+            let path_item = operation
+                .callbacks
+                .get_or_insert_with(Default::default)
+                .entry(key.value().to_string())
+                .or_default()
+                .0
+                .entry(expression)
+                .or_default();
+            method.add_operation(path_item, callback_operation);
+        }
+        Ok(())
+    }
+
+    fn parse_webhook(&mut self, keys: &Keys, key: &Key, value: &Node) -> OpenapiResult<()> {
+        if !self.openapi.is_v3_1() {
+            return Err(OpenapiError::new(
+                keys.join(KeyOrIndex::annotation("@webhook")),
+                "requires openapi 3.1",
+            ));
+        }
+        let name = match value.get(&KeyOrIndex::annotation("@webhook")) {
+            Some(Node::Null(_)) => key.value().to_string(),
+            _ => parse_string_annotation(keys, value, "@webhook")?.unwrap_or_default(),
+        };
+        let (method, operation) = self.parse_detached_operation(keys, value)?;
+        let webhook = self
+            .openapi
+            .webhooks
+            .get_or_insert_with(Default::default)
+            .entry(name)
+            .or_insert_with(|| ObjectOrReference::Object(PathItem::default()));
+        if let ObjectOrReference::Object(path_item) = webhook {
+            if method.operation(path_item).is_some() {
+                return Err(OpenapiError::new(
+                    keys.join(KeyOrIndex::property("route")),
+                    "is conflict",
+                ));
+            }
+            method.add_operation(path_item, operation);
+        }
         Ok(())
     }
 
+    /// Parse an operation which is not served under `paths`, its route only has a method.
+    fn parse_detached_operation(
+        &mut self,
+        keys: &Keys,
+        value: &Node,
+    ) -> OpenapiResult<(MethodKind, Operation)> {
+        if !value.is_object() {
+            return Err(OpenapiError::new(keys.clone(), "must be object"));
+        }
+        let mut operation = self.parse_endpoint_annotation(keys, value)?;
+        if let Some(security) = operation.security.as_ref() {
+            self.check_security(&keys.join(KeyOrIndex::annotation("@endpoint")), security)?;
+        }
+        self.parse_security(&mut operation, keys, value)?;
+        let method = match value.get_as_string("route") {
+            Some((key, Some(route))) => MethodKind::from_str(route.value().trim())
+                .ok_or_else(|| OpenapiError::new(keys.join(key), "is invalid"))?,
+            Some((key, None)) => return Err(OpenapiError::new(keys.join(key), "must be string")),
+            None => return Err(OpenapiError::new(keys.clone(), "miss route")),
+        };
+        self.parse_req(&mut operation, keys, value, &[], false)?;
+        let links_start = self.links.len();
+        let status_keys = self.parse_res(&mut operation, keys, value)?;
+        if let Some(link) = self.links.drain(links_start..).next() {
+            return Err(OpenapiError::new(
+                link.keys,
+                "is only supported in endpoint",
+            ));
+        }
+        self.check_default_response(&operation.responses, &status_keys);
+        if let Some(operation_id) = operation.operation_id.as_ref() {
+            if let Some(other_keys) = self.operation_ids.get(operation_id) {
+                return Err(OpenapiError::new(
+                    keys.clone(),
+                    format!("operationId {operation_id} is conflict with {other_keys}"),
+                ));
+            }
+            self.operation_ids
+                .insert(operation_id.clone(), keys.clone());
+        }
+        Ok((method, operation))
+    }
+
+    fn derive_operation_id(
+        &self,
+        name: &str,
+        method: &MethodKind,
+        pathname: &str,
+        operation: &Operation,
+    ) -> String {
+        let mut words = vec![];
+        if self.options.operation_id_tag_prefix {
+            if let Some(tag) = operation.tags.as_ref().and_then(|v| v.first()) {
+                if self.options.operation_id == OperationIdStrategy::Verbatim {
+                    return format!("{}{}", tag, naming::upper_first(name));
+                }
+                words.extend(naming::split_words(tag));
+            }
+        }
+        match self.options.operation_id {
+            OperationIdStrategy::Verbatim => return name.to_string(),
+            OperationIdStrategy::CamelCase | OperationIdStrategy::SnakeCase => {
+                words.extend(naming::split_words(name));
+            }
+            OperationIdStrategy::MethodPath => {
+                words.push(method.to_string());
+                for part in pathname.split('/') {
+                    if part.starts_with('{') {
+                        words.push("by".into());
+                    }
+                    words.extend(naming::split_words(part));
+                }
+            }
+        }
+        if self.options.operation_id == OperationIdStrategy::SnakeCase {
+            naming::to_snake_case(&words)
+        } else {
+            naming::to_camel_case(&words)
+        }
+    }
+
     fn parse_endpoint_annotation(&mut self, keys: &Keys, value: &Node) -> OpenapiResult<Operation> {
         match value.get_as_object("@endpoint") {
             Some((key, Some(value))) => {
@@ -195,15 +855,94 @@ impl OpenapiParser {
         }
     }
 
+    fn parse_security(
+        &mut self,
+        operation: &mut Operation,
+        keys: &Keys,
+        value: &Node,
+    ) -> OpenapiResult<()> {
+        let security = match (
+            value.get(&KeyOrIndex::annotation("@security")),
+            exist_annotation(value, "@public"),
+        ) {
+            (Some(_), true) => {
+                return Err(OpenapiError::new(
+                    keys.join(KeyOrIndex::annotation("@public")),
+                    "is conflict with @security",
+                ))
+            }
+            (Some(security), false) => {
+                let keys = keys.join(KeyOrIndex::annotation("@security"));
+                let security = match security {
+                    Node::Array(items) => items
+                        .value()
+                        .read()
+                        .iter()
+                        .map(|v| parse_security_requirement(&keys, v))
+                        .collect::<OpenapiResult<Vec<_>>>()?,
+                    _ => vec![parse_security_requirement(&keys, &security)?],
+                };
+                self.check_security(&keys, &security)?;
+                security
+            }
+            (None, true) => vec![],
+            (None, false) => return Ok(()),
+        };
+        if operation.security.is_some() {
+            return Err(OpenapiError::new(
+                keys.clone(),
+                "security is declared in both @endpoint and annotation",
+            ));
+        }
+        operation.security = Some(security);
+        Ok(())
+    }
+
+    fn check_security(&self, keys: &Keys, security: &[SecurityRequirement]) -> OpenapiResult<()> {
+        let schemes = self
+            .openapi
+            .components
+            .as_ref()
+            .and_then(|v| v.security_schemes.as_ref());
+        for requirement in security {
+            for (name, scopes) in requirement {
+                let scheme = match schemes.and_then(|v| v.get(name)) {
+                    Some(ObjectOrReference::Object(v)) => v,
+                    Some(ObjectOrReference::Ref { .. }) => continue,
+                    None => {
+                        return Err(OpenapiError::new(
+                            keys.clone(),
+                            format!("unknown security scheme {name}"),
+                        ))
+                    }
+                };
+                if let SecurityScheme::OAuth2 { flows, .. } = scheme {
+                    let declared_scopes: HashSet<&String> = flows.scopes().collect();
+                    if let Some(scope) = scopes.iter().find(|v| !declared_scopes.contains(v)) {
+                        return Err(OpenapiError::new(
+                            keys.clone(),
+                            format!("unknown scope {scope} of security scheme {name}"),
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn parse_route(
         &mut self,
         keys: &Keys,
         value: &Node,
+        group: &Group,
     ) -> OpenapiResult<(MethodKind, Vec<String>)> {
         match value.get_as_string("route") {
             Some((key, Some(value))) => {
                 let keys = keys.join(key);
-                let splitted_route: Vec<&str> = value.value().split(' ').collect();
+                let mut splitted_route: Vec<&str> = value.value().split(' ').collect();
+                if splitted_route.len() == 1 && !group.prefix.is_empty() {
+                    splitted_route.push("");
+                }
                 let err = || OpenapiError::new(keys.clone(), "is invalid");
                 if splitted_route.len() != 2 {
                     return Err(err());
@@ -211,7 +950,7 @@ impl OpenapiParser {
                 let method = MethodKind::from_str(splitted_route[0]).ok_or_else(err)?;
                 let path = splitted_route[1].trim();
                 let path_parts: Vec<String> = path.split('/').map(|v| v.to_string()).collect();
-                let canonical_route = format!("{} {}", method, path);
+                let canonical_route = format!("{} {}{}", method, group.prefix, path);
                 if !self.routes.insert(canonical_route) {
                     return Err(OpenapiError::new(keys, "is conflict"));
                 }
@@ -228,11 +967,13 @@ impl OpenapiParser {
         keys: &Keys,
         value: &Node,
         path_parts: &[String],
+        params_optional: bool,
     ) -> OpenapiResult<String> {
         match value.get_as_object("req") {
             Some((key, Some(value))) => {
                 let keys = keys.join(key);
-                let pathname = self.parse_req_params(operation, &keys, &value, path_parts)?;
+                let pathname =
+                    self.parse_req_params(operation, &keys, &value, path_parts, params_optional)?;
                 for (key, value) in value.value().read().iter() {
                     match key.value() {
                         "query" => {
@@ -259,7 +1000,7 @@ impl OpenapiParser {
             }
             Some((key, None)) => Err(OpenapiError::new(keys.join(key), "must be object")),
             None => {
-                if path_parts.iter().any(|v| v.as_str() == "{}") {
+                if !params_optional && path_parts.iter().any(|v| v.as_str() == "{}") {
                     return Err(OpenapiError::new(keys.clone(), "req.params is required"));
                 }
                 Ok(path_parts.join("/"))
@@ -273,6 +1014,7 @@ impl OpenapiParser {
         keys: &Keys,
         value: &Object,
         path_parts: &[String],
+        params_optional: bool,
     ) -> OpenapiResult<String> {
         match Node::from(value.clone()).get_as_object("params") {
             Some((key, Some(value))) => {
@@ -298,7 +1040,7 @@ impl OpenapiParser {
             }
             Some((key, None)) => Err(OpenapiError::new(keys.join(key), "must be object")),
             None => {
-                if path_parts.iter().any(|v| v.as_str() == "{}") {
+                if !params_optional && path_parts.iter().any(|v| v.as_str() == "{}") {
                     return Err(OpenapiError::new(keys.clone(), "params is required"));
                 }
                 Ok(path_parts.join("/"))
@@ -350,13 +1092,7 @@ impl OpenapiParser {
         let schema = self.parse_schema(keys, value)?;
         let media_type = MediaType {
             schema: Some(schema),
-            examples: if exist_annotation(value, "@example") {
-                Some(OneOrMultiExample::Example {
-                    example: value.to_plain_json(),
-                })
-            } else {
-                None
-            },
+            examples: self.parse_examples(keys, value)?,
             ..Default::default()
         };
         let mut content = IndexMap::default();
@@ -365,37 +1101,81 @@ impl OpenapiParser {
             description: parse_string_annotation(keys, value, "@describe")?,
             required: Some(true),
             content,
+            extensions: parse_extension_annotation(keys, value)?,
         };
         operation.request_body = Some(ObjectOrReference::Object(request_body));
         Ok(())
     }
 
+    /// Parse the responses of the operation, returns the keys of the declared statuses.
     fn parse_res(
         &mut self,
         operation: &mut Operation,
         keys: &Keys,
         value: &Node,
-    ) -> OpenapiResult<()> {
+    ) -> OpenapiResult<IndexMap<String, Keys>> {
         match value.get_as_object("res") {
             Some((key, Some(value))) => {
                 let keys = keys.join(key);
+                let mut status_keys = IndexMap::default();
                 for (key, value) in value.value().read().iter() {
                     let keys = keys.join(key.clone());
-                    let status = key
-                        .value()
-                        .parse::<u32>()
-                        .map_err(|_| OpenapiError::new(keys.clone(), "should be status code"))?;
-                    if !(100..=599).contains(&status) {
-                        return Err(OpenapiError::new(keys, "must be integer in [100, 600)"));
+                    let status = key.value();
+                    if status != "default" && !status::is_status_range(status) {
+                        let code = status.parse::<u32>().map_err(|_| {
+                            OpenapiError::new(keys.clone(), "should be status code")
+                        })?;
+                        if !(100..=599).contains(&code) {
+                            return Err(OpenapiError::new(keys, "must be integer in [100, 600)"));
+                        }
                     }
-                    let description =
-                        parse_string_annotation(&keys, value, "@describe")?.unwrap_or_default();
+                    status_keys.insert(status.to_string(), keys.clone());
+                    let description = match parse_string_annotation(&keys, value, "@describe")? {
+                        Some(description) => description,
+                        None => match status::default_description(status) {
+                            Some(phrase) => phrase.to_string(),
+                            None => {
+                                self.warnings.push(OpenapiError::new(
+                                    keys.clone(),
+                                    "response has no description, use @describe",
+                                ));
+                                String::new()
+                            }
+                        },
+                    };
                     let mut response = Response {
                         description,
+                        extensions: parse_extension_annotation(&keys, value)?,
                         ..Default::default()
                     };
 
+                    let links: IndexMap<String, LinkOptions> =
+                        parse_object_annotation(&keys, value, "@link")?;
+                    for (name, options) in links {
+                        self.links.push(PendingLink {
+                            keys: keys
+                                .join(KeyOrIndex::annotation("@link"))
+                                .join(KeyOrIndex::property(name.clone())),
+                            location: None,
+                            file_location: None,
+                            status: status.to_string(),
+                            name,
+                            options,
+                        });
+                    }
+
                     let with_header = exist_annotation(value, "@withHeader");
+                    let bodyless = status::is_bodyless(status);
+                    let no_content = match parse_bool_annotation(&keys, value, "@noContent")? {
+                        Some(false) if bodyless => {
+                            return Err(OpenapiError::new(
+                                keys.join(KeyOrIndex::annotation("@noContent")),
+                                format!("status {status} must not have body"),
+                            ))
+                        }
+                        Some(no_content) => no_content || bodyless,
+                        None => bodyless,
+                    };
 
                     if with_header {
                         match value.as_object() {
@@ -411,6 +1191,7 @@ impl OpenapiParser {
                                             &mut response,
                                             &keys.join(key.clone()),
                                             value,
+                                            no_content,
                                         )?,
                                         _ => {}
                                     }
@@ -421,60 +1202,813 @@ impl OpenapiParser {
                             }
                         }
                     } else {
-                        self.parse_res_body(&mut response, &keys, value)?;
+                        self.parse_res_body(&mut response, &keys, value, no_content)?;
                     }
 
                     operation.responses.insert(status.to_string(), response);
                 }
-                Ok(())
+                Ok(status_keys)
             }
             Some((key, None)) => Err(OpenapiError::new(keys.join(key), "must be object")),
             None => {
                 let default_response = Response {
-                    description: Default::default(),
+                    description: status::default_description("200")
+                        .unwrap_or_default()
+                        .into(),
                     ..Default::default()
                 };
                 operation.responses.insert("200".into(), default_response);
-                Ok(())
+                Ok(IndexMap::default())
             }
         }
     }
 
-    fn parse_parameter(
+    /// Warn on responses that the default response shadows, which are the non-2xx responses
+    /// with the same content and headers as the default. Responses with a different content
+    /// are distinct errors and 2xx responses are never covered by `default`, so neither warns.
+    /// `status_keys` are the responses declared by the operation, the others are inherited and
+    /// already checked.
+    fn check_default_response(
         &mut self,
-        mut parameter: Parameter,
-        keys: &Keys,
-        value: &Node,
-    ) -> OpenapiResult<ObjectOrReference<Parameter>> {
-        if let Some(ref_val) = parse_ref_annotation(keys, value, "#/components/parameters/")? {
-            return Ok(ref_val);
+        responses: &IndexMap<String, Response>,
+        status_keys: &IndexMap<String, Keys>,
+    ) {
+        let default_response = match responses.get("default") {
+            Some(v) => v,
+            None => return,
+        };
+        for (status, response) in responses.iter() {
+            if status == "default"
+                || status.starts_with('2')
+                || response.content.is_none()
+                || response.content != default_response.content
+                || response.headers != default_response.headers
+            {
+                continue;
+            }
+            match (status_keys.get(status), status_keys.get("default")) {
+                (Some(keys), _) => self.warnings.push(OpenapiError::new(
+                    keys.clone(),
+                    "is identical to the default response",
+                )),
+                (None, Some(keys)) => self.warnings.push(OpenapiError::new(
+                    keys.clone(),
+                    format!("is identical to the inherited response {status}"),
+                )),
+                (None, None) => {}
+            }
         }
-        parameter.description = parse_string_annotation(keys, value, "@describe")?;
-        parameter.required = Some(!exist_annotation(value, "@optional"));
-        parameter.schema = Some(self.parse_schema(keys, value)?);
-        parameter.examples = if exist_annotation(value, "@example") {
-            Some(OneOrMultiExample::Example {
-                example: value.to_plain_json(),
+    }
+
+    fn parse_links(&mut self) {
+        for link in std::mem::take(&mut self.links) {
+            let file_location = link.file_location.clone();
+            if let Err(mut error) = self.parse_link(link) {
+                error.location = file_location.map(Box::new);
+                self.errors.push(error);
+            }
+        }
+    }
+
+    fn parse_link(&mut self, link: PendingLink) -> OpenapiResult<()> {
+        let PendingLink {
+            keys,
+            location,
+            status,
+            name,
+            options,
+            ..
+        } = link;
+        let (pathname, method) = match location {
+            Some(v) => v,
+            None => return Ok(()),
+        };
+        let (target_pathname, target_method) = match self
+            .endpoints
+            .get(&options.endpoint)
+            .map(|v| v.as_slice())
+            .unwrap_or_default()
+        {
+            [target] => target.clone(),
+            [] => {
+                return Err(OpenapiError::new(
+                    keys.join(KeyOrIndex::property("endpoint")),
+                    format!("unknown endpoint {}", options.endpoint),
+                ))
+            }
+            _ => {
+                return Err(OpenapiError::new(
+                    keys.join(KeyOrIndex::property("endpoint")),
+                    format!("ambiguous endpoint {}", options.endpoint),
+                ))
+            }
+        };
+        let target_item = &self.openapi.paths[&target_pathname];
+        let target = target_method
+            .operation(target_item)
+            .ok_or_else(|| OpenapiError::new(keys.clone(), "unknown target operation"))?;
+        let components_parameters = self
+            .openapi
+            .components
+            .as_ref()
+            .and_then(|v| v.parameters.as_ref());
+        let target_parameters: Vec<&Parameter> = target
+            .parameters
+            .iter()
+            .chain(target_item.parameters.iter())
+            .flatten()
+            .filter_map(|v| match v {
+                ObjectOrReference::Object(v) => Some(v),
+                ObjectOrReference::Ref { ref_path } => ref_path
+                    .strip_prefix(PARAMETER_REF_PREFIX)
+                    .and_then(|name| components_parameters.and_then(|v| v.get(name)))
+                    .and_then(|v| match v {
+                        ObjectOrReference::Object(v) => Some(v),
+                        ObjectOrReference::Ref { .. } => None,
+                    }),
             })
-        } else {
-            None
+            .collect();
+        for parameter_name in options.parameters.iter().flat_map(|v| v.keys()) {
+            let exist = target_parameters
+                .iter()
+                .any(|v| match parameter_name.split_once('.') {
+                    Some((location, name)) if v.location == location => v.name == name,
+                    _ => &v.name == parameter_name,
+                });
+            if !exist {
+                return Err(OpenapiError::new(
+                    keys.join(KeyOrIndex::property("parameters"))
+                        .join(KeyOrIndex::property(parameter_name.clone())),
+                    format!(
+                        "unknown parameter {} of endpoint {}",
+                        parameter_name, options.endpoint
+                    ),
+                ));
+            }
+        }
+        let link = Link::Id {
+            operation_id: target.operation_id.clone().unwrap_or_default(),
+            parameters: options.parameters,
+            request_body: options.request_body,
+            description: options.description,
+            server: options.server,
+            extensions: Extensions::default(),
         };
+        if let Some(response) = self
+            .openapi
+            .paths
+            .get_mut(&pathname)
+            .and_then(|v| method.operation_mut(v))
+            .and_then(|v| v.responses.get_mut(&status))
+        {
+            response
+                .links
+                .get_or_insert(Default::default())
+                .insert(name, ObjectOrReference::Object(link));
+        }
+        Ok(())
+    }
 
-        let parameter_object = ObjectOrReference::Object(parameter);
-
-        if let Some(name) = parse_string_annotation(keys, value, "@def")? {
-            return self.def_parameters(name, parameter_object);
+    /// Register placeholder defs for `@ref` pointing into other files, so the schema parser
+    /// accepts them. They are resolved by `parse_external_refs` after all paths are parsed.
+    fn register_external_refs(&mut self, keys: &Keys, value: &Node) -> OpenapiResult<()> {
+        if let Some(Node::String(ref_value)) = value.get(&KeyOrIndex::annotation("@ref")) {
+            let placeholder = ref_value.value();
+            if self
+                .register_external_ref(keys, placeholder, RefKind::Schema)?
+                .is_some()
+            {
+                self.defs
+                    .borrow_mut()
+                    .entry(placeholder.to_string())
+                    .or_default();
+            }
         }
-        Ok(parameter_object)
+        match value {
+            Node::Object(object) => {
+                for (key, value) in object.value().read().iter() {
+                    self.register_external_refs(&keys.join(key.clone()), value)?;
+                }
+            }
+            Node::Array(array) => {
+                for (index, value) in array.value().read().iter().enumerate() {
+                    self.register_external_refs(&keys.join(KeyOrIndex::Index(index)), value)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
     }
 
-    fn parse_res_header(
+    /// Record a `@ref` with a file path, e.g. `common.jsona#Error`, returns the local ref path
+    /// to emit until `parse_external_refs` rewrites it.
+    fn register_external_ref(
         &mut self,
-        response: &mut Response,
         keys: &Keys,
-        value: &Node,
-    ) -> OpenapiResult<()> {
-        match value.as_object() {
+        placeholder: &str,
+        kind: RefKind,
+    ) -> OpenapiResult<Option<String>> {
+        let (path, fragment) = match placeholder.split_once('#') {
+            Some((path, fragment)) if !path.is_empty() => (path, fragment),
+            _ => return Ok(None),
+        };
+        let file = self.dir.join(path);
+        let ref_path = format!("{}{placeholder}", kind.prefix());
+        match self.external_refs.get(&ref_path) {
+            Some(external_ref) if external_ref.file != file => {
+                return Err(OpenapiError::new(
+                    keys.join(KeyOrIndex::annotation("@ref")),
+                    format!(
+                        "is conflict with {} which refers to {}",
+                        external_ref.keys,
+                        external_ref.file.display()
+                    ),
+                ));
+            }
+            Some(_) => {}
+            None => {
+                self.external_refs.insert(
+                    ref_path.clone(),
+                    ExternalRef {
+                        keys: keys.clone(),
+                        file_location: None,
+                        file,
+                        fragment: fragment.to_string(),
+                        kind,
+                    },
+                );
+            }
+        }
+        Ok(Some(ref_path))
+    }
+
+    /// Resolve external refs, refs into json files are kept in [`ExternalRefMode::Keep`] mode
+    /// once the target is found, everything else is bundled into components.
+    fn parse_external_refs(&mut self) {
+        let external_refs = std::mem::take(&mut self.external_refs);
+        if external_refs.is_empty() {
+            return;
+        }
+        let mut refs = HashMap::default();
+        for (ref_path, external_ref) in external_refs {
+            if external_ref.kind == RefKind::Schema {
+                self.defs
+                    .borrow_mut()
+                    .shift_remove(&ref_path[SCHEMA_REF_PREFIX.len()..]);
+            }
+            let keys = &external_ref.keys;
+            let file = &external_ref.file;
+            let pointer = external_pointer(file, &external_ref.fragment, external_ref.kind);
+            let target = match self.options.external_refs {
+                ExternalRefMode::Keep if !is_jsona_file(file) => self
+                    .load_external_document(keys, file)
+                    .and_then(|document| match document.pointer(&pointer) {
+                        Some(_) => Ok(()),
+                        None => Err(vec![OpenapiError::new(
+                            keys.clone(),
+                            format!("unknown ref {}#{}", file.display(), pointer),
+                        )]),
+                    })
+                    .map(|_| {
+                        let file = file.strip_prefix(&self.root_dir).unwrap_or(file);
+                        format!("{}#{}", file.display(), pointer)
+                    }),
+                _ => self
+                    .import_external(keys, file, &pointer, external_ref.kind)
+                    .map(|name| format!("{}{name}", external_ref.kind.prefix())),
+            };
+            match target {
+                Ok(target) => {
+                    refs.insert(ref_path, target);
+                }
+                Err(errors) => {
+                    for mut error in errors {
+                        if error.location.is_none() {
+                            error.location = external_ref.file_location.clone().map(Box::new);
+                        }
+                        self.errors.push(error);
+                    }
+                }
+            }
+        }
+        self.rewrite_refs(&refs);
+    }
+
+    /// Replace `$ref` values in defs and the whole document.
+    fn rewrite_refs(&mut self, refs: &HashMap<String, String>) {
+        if refs.is_empty() {
+            return;
+        }
+        for schema in self.defs.borrow_mut().values_mut() {
+            let mut value = serde_json::to_value(&*schema).unwrap();
+            if rewrite_refs(&mut value, refs) {
+                *schema = serde_json::from_value(value).unwrap();
+            }
+        }
+        let mut value = serde_json::to_value(&self.openapi).unwrap();
+        if rewrite_refs(&mut value, refs) {
+            self.openapi = serde_json::from_value(value).unwrap();
+        }
+    }
+
+    /// Scan `@def("Page<T>")` template declarations and `@ref("Page<Pet>")` instances, insert
+    /// placeholder defs for type parameters and instances so that the schema can be parsed.
+    /// Type parameters can only be referred to in the body of their template.
+    fn register_generics(&mut self, keys: &Keys, value: &Node) -> OpenapiResult<()> {
+        self.register_generics_in(keys, value, &[])
+    }
+
+    fn register_generics_in(
+        &mut self,
+        keys: &Keys,
+        value: &Node,
+        scope: &[String],
+    ) -> OpenapiResult<()> {
+        let mut scope = scope.to_vec();
+        if let Some(Node::String(def)) = value.get(&KeyOrIndex::annotation("@def")) {
+            let def_keys = keys.join(KeyOrIndex::annotation("@def"));
+            if def.value().contains('<') {
+                scope.extend(self.register_template(&def_keys, def.value())?);
+            } else if self.template_params.contains(def.value()) {
+                return Err(OpenapiError::new(
+                    def_keys,
+                    format!("is conflict with type parameter {}", def.value()),
+                ));
+            }
+        }
+        if let Some(Node::String(ref_value)) = value.get(&KeyOrIndex::annotation("@ref")) {
+            let ref_value = ref_value.value();
+            if ref_value.contains('<') {
+                self.register_generic_ref(
+                    &keys.join(KeyOrIndex::annotation("@ref")),
+                    ref_value,
+                    &scope,
+                )?;
+            } else if self.template_params.contains(ref_value)
+                && !scope.iter().any(|v| v == ref_value)
+            {
+                return Err(OpenapiError::new(
+                    keys.clone(),
+                    format!("unknown def {ref_value}"),
+                ));
+            }
+        }
+        match value {
+            Node::Object(object) => {
+                for (key, value) in object.value().read().iter() {
+                    self.register_generics_in(&keys.join(key.clone()), value, &scope)?;
+                }
+            }
+            Node::Array(array) => {
+                for (index, value) in array.value().read().iter().enumerate() {
+                    self.register_generics_in(&keys.join(KeyOrIndex::Index(index)), value, &scope)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Register a template, returns its type parameters.
+    fn register_template(&mut self, keys: &Keys, def: &str) -> OpenapiResult<Vec<String>> {
+        let (name, params) = parse_generic(def)
+            .filter(|(_, params)| params.iter().all(|v| is_identifier(v)))
+            .ok_or_else(|| OpenapiError::new(keys.clone(), "is invalid generic"))?;
+        if self.templates.contains_key(&name) {
+            return Err(OpenapiError::new(keys.clone(), "is conflict"));
+        }
+        let mut defs = self.defs.borrow_mut();
+        for param in params.iter() {
+            if !self.template_params.contains(param) {
+                if defs.contains_key(param) {
+                    return Err(OpenapiError::new(
+                        keys.clone(),
+                        format!("type parameter {param} is conflict with def"),
+                    ));
+                }
+                defs.insert(param.clone(), Schema::default());
+                self.template_params.insert(param.clone());
+            }
+        }
+        self.templates.insert(
+            name,
+            Template {
+                def: def.to_string(),
+                params: params.clone(),
+            },
+        );
+        Ok(params)
+    }
+
+    fn register_generic_ref(
+        &mut self,
+        keys: &Keys,
+        ref_value: &str,
+        scope: &[String],
+    ) -> OpenapiResult<()> {
+        let params: Vec<&str> = ref_value
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .skip(1)
+            .filter(|v| self.template_params.contains(*v))
+            .collect();
+        if let Some(param) = params.iter().find(|v| !scope.iter().any(|s| s == *v)) {
+            return Err(OpenapiError::new(
+                keys.clone(),
+                format!("unknown def {param}"),
+            ));
+        }
+        if self.generic_refs.contains_key(ref_value) || self.template_refs.contains(ref_value) {
+            return Ok(());
+        }
+        let (name, args) = parse_generic(ref_value)
+            .ok_or_else(|| OpenapiError::new(keys.clone(), "is invalid generic"))?;
+        let template = self.templates.get(&name).ok_or_else(|| {
+            OpenapiError::new(keys.clone(), format!("unknown generic def {name}"))
+        })?;
+        if template.params.len() != args.len() {
+            return Err(OpenapiError::new(
+                keys.clone(),
+                format!("{name} expects {} type arguments", template.params.len()),
+            ));
+        }
+        if ref_value.matches('<').count() > MAX_GENERIC_DEPTH {
+            return Err(OpenapiError::new(keys.clone(), "is nested too deep"));
+        }
+        for arg in args.iter() {
+            if arg.contains('<') {
+                self.register_generic_ref(keys, arg, scope)?;
+            } else if !self.defs.borrow().contains_key(arg) {
+                return Err(OpenapiError::new(
+                    keys.clone(),
+                    format!("unknown def {arg}"),
+                ));
+            }
+        }
+        if !params.is_empty() {
+            // Refers to type parameters, instantiated by substituting the enclosing template.
+            self.defs
+                .borrow_mut()
+                .entry(ref_value.to_string())
+                .or_default();
+            self.template_refs.insert(ref_value.to_string());
+            return Ok(());
+        }
+        self.defs
+            .borrow_mut()
+            .insert(ref_value.to_string(), Schema::default());
+        self.generic_refs.insert(
+            ref_value.to_string(),
+            GenericRef {
+                keys: keys.clone(),
+                file_location: None,
+            },
+        );
+        Ok(())
+    }
+
+    /// Monomorphise instances of generic defs, e.g. `Page<Pet>` into `PagePet`.
+    fn parse_generics(&mut self) {
+        if self.templates.is_empty() {
+            return;
+        }
+        let mut instances: IndexMap<String, String> = IndexMap::default();
+        let mut index = 0;
+        while let Some((ref_value, generic_ref)) = self
+            .generic_refs
+            .get_index(index)
+            .map(|(k, v)| (k.clone(), v.clone()))
+        {
+            index += 1;
+            match self.instantiate_generic(&ref_value, &generic_ref) {
+                Ok(name) => {
+                    instances.insert(ref_value, name);
+                }
+                Err(mut error) => {
+                    if error.location.is_none() {
+                        error.location = generic_ref.file_location.map(Box::new);
+                    }
+                    self.errors.push(error);
+                }
+            }
+        }
+        let mut refs = HashMap::default();
+        {
+            let mut defs = self.defs.borrow_mut();
+            for template in self.templates.values() {
+                defs.shift_remove(&template.def);
+            }
+            for param in self.template_params.iter().chain(self.template_refs.iter()) {
+                defs.shift_remove(param);
+            }
+            for (ref_value, name) in instances {
+                if let Some(schema) = defs.shift_remove(&ref_value) {
+                    defs.entry(name.clone()).or_insert(schema);
+                }
+                refs.insert(
+                    format!("{SCHEMA_REF_PREFIX}{ref_value}"),
+                    format!("{SCHEMA_REF_PREFIX}{name}"),
+                );
+            }
+        }
+        self.rewrite_refs(&refs);
+    }
+
+    /// Substitute the type parameters of the template, returns the name of the instance.
+    fn instantiate_generic(
+        &mut self,
+        ref_value: &str,
+        generic_ref: &GenericRef,
+    ) -> OpenapiResult<String> {
+        let (name, args) = parse_generic(ref_value).unwrap();
+        let Template { def, params } = &self.templates[&name];
+        let (def, params) = (def.clone(), params.clone());
+        let instance_name = generic_instance_name(ref_value);
+        if !self.generic_refs.contains_key(&instance_name)
+            && self.defs.borrow().contains_key(&instance_name)
+        {
+            return Err(OpenapiError::new(
+                generic_ref.keys.clone(),
+                format!("instance {instance_name} is conflict with def"),
+            ));
+        }
+        let substitutions: HashMap<&str, &str> = params
+            .iter()
+            .map(|v| v.as_str())
+            .zip(args.iter().map(|v| v.as_str()))
+            .collect();
+        let mut value = serde_json::to_value(&self.defs.borrow()[&def]).unwrap();
+        let mut refs = HashMap::default();
+        for target in collect_refs(&value) {
+            let def = match target.strip_prefix(SCHEMA_REF_PREFIX) {
+                Some(def) => def,
+                None => continue,
+            };
+            let substituted = substitute_generic(def, &substitutions);
+            if substituted != def {
+                if substituted.contains('<') {
+                    self.register_generic_ref(&generic_ref.keys, &substituted, &[])?;
+                }
+                refs.insert(target.clone(), format!("{SCHEMA_REF_PREFIX}{substituted}"));
+            }
+        }
+        rewrite_refs(&mut value, &refs);
+        self.defs.borrow_mut().insert(
+            ref_value.to_string(),
+            serde_json::from_value(value).unwrap(),
+        );
+        Ok(instance_name)
+    }
+
+    /// Copy the schema or parameter at `pointer` of an external file into components, returns
+    /// the name.
+    fn import_external(
+        &mut self,
+        keys: &Keys,
+        file: &Path,
+        pointer: &str,
+        kind: RefKind,
+    ) -> Result<String, Vec<OpenapiError>> {
+        let id = (file.to_path_buf(), pointer.to_string(), kind);
+        if let Some(name) = self.imported_refs.get(&id) {
+            return Ok(name.clone());
+        }
+        let document = self.load_external_document(keys, file)?;
+        let mut value = document.pointer(pointer).cloned().ok_or_else(|| {
+            vec![OpenapiError::new(
+                keys.clone(),
+                format!("unknown ref {}#{}", file.display(), pointer),
+            )]
+        })?;
+        let name = match pointer.rsplit('/').next() {
+            Some(name) if !name.is_empty() => name.replace("~1", "/").replace("~0", "~"),
+            _ => file
+                .file_stem()
+                .map(|v| v.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        };
+        let name = self.unique_name(file, &name, kind);
+        if kind == RefKind::Schema {
+            // Reserve the name for recursive schemas, parameters never refer to parameters.
+            self.defs
+                .borrow_mut()
+                .insert(name.clone(), Schema::default());
+        }
+        self.imported_refs.insert(id, name.clone());
+        let mut refs = HashMap::default();
+        for ref_value in collect_refs(&value) {
+            let (path, fragment) = ref_value.split_once('#').unwrap_or((&ref_value, ""));
+            let ref_file = if path.is_empty() {
+                file.to_path_buf()
+            } else {
+                file.parent().unwrap_or_else(|| Path::new("")).join(path)
+            };
+            let ref_kind = if fragment.starts_with("/components/parameters/") {
+                RefKind::Parameter
+            } else {
+                RefKind::Schema
+            };
+            let pointer = external_pointer(&ref_file, fragment, ref_kind);
+            let ref_name = self.import_external(keys, &ref_file, &pointer, ref_kind)?;
+            refs.insert(ref_value, format!("{}{ref_name}", ref_kind.prefix()));
+        }
+        rewrite_refs(&mut value, &refs);
+        self.insert_imported(&name, kind, value).map_err(|error| {
+            vec![OpenapiError::new(
+                keys.clone(),
+                format!(
+                    "invalid {} {}#{}, {error}",
+                    kind.name(),
+                    file.display(),
+                    pointer
+                ),
+            )]
+        })?;
+        Ok(name)
+    }
+
+    fn insert_imported(
+        &mut self,
+        name: &str,
+        kind: RefKind,
+        value: Value,
+    ) -> serde_json::Result<()> {
+        match kind {
+            RefKind::Schema => {
+                let schema: Schema = serde_json::from_value(value)?;
+                self.defs.borrow_mut().insert(name.to_string(), schema);
+            }
+            RefKind::Parameter => {
+                let parameter: ObjectOrReference<Parameter> = serde_json::from_value(value)?;
+                get_components_mut(&mut self.openapi)
+                    .parameters
+                    .get_or_insert_with(Default::default)
+                    .insert(name.to_string(), parameter);
+            }
+        }
+        Ok(())
+    }
+
+    /// Load an external json file, or the defs of an external jsona file as
+    /// `{"components":{"schemas":{...}}}`.
+    fn load_external_document(
+        &mut self,
+        keys: &Keys,
+        file: &Path,
+    ) -> Result<Rc<Value>, Vec<OpenapiError>> {
+        if let Some(document) = self.external_documents.get(file) {
+            return Ok(document.clone());
+        }
+        let source = std::fs::read_to_string(file).map_err(|error| {
+            vec![OpenapiError::new(
+                keys.clone(),
+                format!("failed to read {}, {error}", file.display()),
+            )]
+        })?;
+        let document = if is_jsona_file(file) {
+            let canonical_file = canonicalize(file);
+            if self.files.contains(&canonical_file) {
+                return Err(vec![OpenapiError::new(
+                    keys.clone(),
+                    format!("circular ref {}", file.display()),
+                )]);
+            }
+            let (node, mapper) = load_file(file, &source)?;
+            let mut parser = Self::new(&node, &self.options);
+            parser.dir = file.parent().map(|v| v.to_path_buf()).unwrap_or_default();
+            parser.root_dir = self.root_dir.clone();
+            parser.files = self.files.clone();
+            parser.files.push(canonical_file);
+            parser.parse_paths(&node);
+            parser.attach_file(Checkpoint::default(), file, &node, &mapper);
+            parser.parse_generics();
+            parser.parse_external_refs();
+            parser.parse_deferred();
+            if !parser.errors.is_empty() {
+                return Err(parser.errors);
+            }
+            self.warnings.append(&mut parser.warnings);
+            let schemas = parser.defs.take();
+            let parameters = parser
+                .openapi
+                .components
+                .and_then(|v| v.parameters)
+                .unwrap_or_default();
+            serde_json::json!({ "components": { "schemas": schemas, "parameters": parameters } })
+        } else {
+            serde_json::from_str(&source).map_err(|error| {
+                vec![OpenapiError::new(
+                    keys.clone(),
+                    format!("invalid json file {}, {error}", file.display()),
+                )]
+            })?
+        };
+        let document = Rc::new(document);
+        self.external_documents
+            .insert(file.to_path_buf(), document.clone());
+        Ok(document)
+    }
+
+    fn unique_name(&self, file: &Path, name: &str, kind: RefKind) -> String {
+        let defs = self.defs.borrow();
+        let parameters = self
+            .openapi
+            .components
+            .as_ref()
+            .and_then(|v| v.parameters.as_ref());
+        let exists = |name: &str| match kind {
+            RefKind::Schema => defs.contains_key(name),
+            RefKind::Parameter => parameters.map(|v| v.contains_key(name)).unwrap_or_default(),
+        };
+        if !exists(name) {
+            return name.to_string();
+        }
+        let mut words = file
+            .file_stem()
+            .map(|v| naming::split_words(&v.to_string_lossy()))
+            .unwrap_or_default();
+        words.extend(naming::split_words(name));
+        let prefixed_name = naming::to_pascal_case(&words);
+        if !exists(&prefixed_name) {
+            return prefixed_name;
+        }
+        (2..)
+            .map(|index| format!("{prefixed_name}{index}"))
+            .find(|v| !exists(v))
+            .unwrap()
+    }
+
+    fn parse_parameter(
+        &mut self,
+        mut parameter: Parameter,
+        keys: &Keys,
+        value: &Node,
+    ) -> OpenapiResult<ObjectOrReference<Parameter>> {
+        if let Some(ref_value) = parse_string_annotation(keys, value, "@ref")? {
+            let ref_path = match self.register_external_ref(keys, &ref_value, RefKind::Parameter)? {
+                Some(ref_path) => ref_path,
+                None => format!("{PARAMETER_REF_PREFIX}{ref_value}"),
+            };
+            return Ok(ObjectOrReference::Ref { ref_path });
+        }
+        parameter.description = parse_string_annotation(keys, value, "@describe")?;
+        parameter.required = Some(!exist_annotation(value, "@optional"));
+        parameter.extensions = parse_extension_annotation(keys, value)?;
+        self.parse_parameter_style(&mut parameter, keys, value)?;
+        parameter.schema = Some(self.parse_schema(keys, value)?);
+        parameter.examples = self.parse_examples(keys, value)?;
+
+        let parameter_object = ObjectOrReference::Object(parameter);
+
+        if let Some(name) = parse_string_annotation(keys, value, "@def")? {
+            return self.def_parameters(name, parameter_object);
+        }
+        Ok(parameter_object)
+    }
+
+    fn parse_parameter_style(
+        &mut self,
+        parameter: &mut Parameter,
+        keys: &Keys,
+        value: &Node,
+    ) -> OpenapiResult<()> {
+        let location = parameter.location.as_str();
+        if let Some((key, style)) = value.get_as_string("@style") {
+            let keys = keys.join(key);
+            let style = style.ok_or_else(|| OpenapiError::new(keys.clone(), "must be string"))?;
+            let style: ParameterStyle =
+                serde_json::from_value(Value::String(style.value().to_string()))
+                    .map_err(|_| OpenapiError::new(keys.clone(), "is invalid"))?;
+            if !style.is_allowed_in(location) {
+                return Err(OpenapiError::new(
+                    keys,
+                    format!("cannot be used by {location} parameter"),
+                ));
+            }
+            parameter.style = Some(style);
+        }
+        parameter.explode = parse_bool_annotation(keys, value, "@explode")?;
+        for (name, field) in [
+            ("@allowReserved", &mut parameter.allow_reserved),
+            ("@allowEmptyValue", &mut parameter.allow_empty_value),
+        ] {
+            *field = parse_bool_annotation(keys, value, name)?;
+            if field.is_some() && location != "query" {
+                return Err(OpenapiError::new(
+                    keys.join(KeyOrIndex::annotation(name)),
+                    format!("cannot be used by {location} parameter"),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_res_header(
+        &mut self,
+        response: &mut Response,
+        keys: &Keys,
+        value: &Node,
+    ) -> OpenapiResult<()> {
+        match value.as_object() {
             Some(value) => {
                 for (key, value) in value.value().read().iter() {
                     let keys = keys.join(key.clone());
@@ -482,6 +2016,7 @@ impl OpenapiParser {
                         description: parse_string_annotation(&keys, value, "@describe")?,
                         required: Some(!exist_annotation(value, "@optional")),
                         schema: Some(self.parse_schema(&keys, value)?),
+                        extensions: parse_extension_annotation(&keys, value)?,
                         ..Default::default()
                     };
                     let header_object = ObjectOrReference::Object(header);
@@ -501,19 +2036,21 @@ impl OpenapiParser {
         response: &mut Response,
         keys: &Keys,
         value: &Node,
+        no_content: bool,
     ) -> OpenapiResult<()> {
+        if no_content {
+            return match value {
+                Node::Null(_) => Ok(()),
+                Node::String(value) if value.value().is_empty() => Ok(()),
+                _ => Err(OpenapiError::new(keys.clone(), "must not have body")),
+            };
+        }
         let content_type = parse_string_annotation(keys, value, "@contentType")?
             .unwrap_or_else(|| "application/json".into());
         let schema = self.parse_schema(keys, value)?;
         let media_type = MediaType {
             schema: Some(schema),
-            examples: if exist_annotation(value, "@example") {
-                Some(OneOrMultiExample::Example {
-                    example: value.to_plain_json(),
-                })
-            } else {
-                None
-            },
+            examples: self.parse_examples(keys, value)?,
             ..Default::default()
         };
         response
@@ -523,21 +2060,852 @@ impl OpenapiParser {
         Ok(())
     }
 
+    fn parse_examples(
+        &self,
+        keys: &Keys,
+        value: &Node,
+    ) -> OpenapiResult<Option<OneOrMultiExample>> {
+        let files = match value.get(&KeyOrIndex::annotation("@exampleFile")) {
+            Some(files) => files,
+            None if exist_annotation(value, "@example") => {
+                return Ok(Some(OneOrMultiExample::Example {
+                    example: value.to_plain_json(),
+                }))
+            }
+            None => return Ok(None),
+        };
+        let keys = keys.join(KeyOrIndex::annotation("@exampleFile"));
+        if exist_annotation(value, "@example") {
+            return Err(OpenapiError::new(keys, "is conflict with @example"));
+        }
+        let files: Vec<(String, String)> = match files {
+            Node::String(path) => {
+                let path = path.value().to_string();
+                let name = Path::new(&path)
+                    .file_stem()
+                    .map(|v| v.to_string_lossy().to_string())
+                    .unwrap_or_else(|| path.clone());
+                vec![(name, path)]
+            }
+            Node::Object(object) => object
+                .value()
+                .read()
+                .iter()
+                .map(|(key, value)| match value.as_string() {
+                    Some(path) => Ok((key.value().to_string(), path.value().to_string())),
+                    None => Err(OpenapiError::new(keys.join(key.clone()), "must be string")),
+                })
+                .collect::<OpenapiResult<_>>()?,
+            _ => return Err(OpenapiError::new(keys, "must be string or object")),
+        };
+        let mut examples = IndexMap::default();
+        for (name, path) in files {
+            let value = if self.options.bundle_example_files && path.ends_with(".json") {
+                let file_path = self.resolve_path(&path);
+                let content = std::fs::read_to_string(&file_path).map_err(|error| {
+                    OpenapiError::new(keys.clone(), format!("failed to read {path}, {error}"))
+                })?;
+                let value = serde_json::from_str(&content).map_err(|error| {
+                    OpenapiError::new(keys.clone(), format!("invalid json file {path}, {error}"))
+                })?;
+                ExampleValue::Value { value }
+            } else {
+                // Relative to the root document like external refs, not to the included file.
+                let file_path = self.resolve_path(&path);
+                let file_path = file_path.strip_prefix(&self.root_dir).unwrap_or(&file_path);
+                ExampleValue::External {
+                    external_value: file_path.display().to_string(),
+                }
+            };
+            let example = Example {
+                value: Some(value),
+                ..Default::default()
+            };
+            examples.insert(name, ObjectOrReference::Object(example));
+        }
+        Ok(Some(OneOrMultiExample::Examples { examples }))
+    }
+
+    fn resolve_path(&self, path: &str) -> PathBuf {
+        self.dir.join(path)
+    }
+
     fn parse_schema(&mut self, keys: &Keys, value: &Node) -> OpenapiResult<Schema> {
+        self.register_external_refs(keys, value)?;
+        self.register_generics(keys, value)?;
         let scope = SchemaParser {
             keys: keys.clone(),
             node: value.clone(),
             defs: self.defs.clone(),
-            ref_prefix: Rc::new("#/components/schemas/".to_string()),
+            ref_prefix: Rc::new(SCHEMA_REF_PREFIX.to_string()),
             prefer_optional: false,
         };
-        let mut schema = scope
-            .parse()
-            .map_err(|_| OpenapiError::new(keys.clone(), "invalid schema"))?;
+        let mut schema = scope.parse().map_err(|errors| match errors.first() {
+            Some(SchemaError::UnknownRef { keys, name }) => {
+                OpenapiError::new(keys.clone(), format!("unknown def {name}"))
+            }
+            _ => OpenapiError::new(keys.clone(), "invalid schema"),
+        })?;
         schema.description = None;
+        self.refine_schema(keys, value, &mut schema, self.options.infer_formats)?;
         Ok(schema)
     }
 
+    /// Apply the annotations unknown to jsona-schema, walking the node and its schema in
+    /// parallel. `@inferFormat(bool)` overrides the option for a node and its children.
+    fn refine_schema(
+        &mut self,
+        keys: &Keys,
+        value: &Node,
+        schema: &mut Schema,
+        infer_format: bool,
+    ) -> OpenapiResult<()> {
+        let infer_format =
+            parse_bool_annotation(keys, value, "@inferFormat")?.unwrap_or(infer_format);
+        if exist_annotation(value, "@ref") {
+            // Refs are refined where the def is declared.
+        } else if let Some(def) = parse_string_annotation(keys, value, "@def")? {
+            self.def_nodes.insert(def.clone(), value.clone());
+            let mut def_schema = match self.defs.borrow_mut().get_mut(&def) {
+                Some(def_schema) => std::mem::take(def_schema),
+                None => return Ok(()),
+            };
+            let result = self.refine_schema_node(keys, value, &mut def_schema, infer_format);
+            if result.is_ok() {
+                add_enum_varnames(&mut def_schema);
+            }
+            self.defs.borrow_mut().insert(def, def_schema);
+            result?;
+        } else {
+            self.refine_schema_node(keys, value, schema, infer_format)?;
+        }
+        self.parse_nullable(keys, value, schema)
+    }
+
+    /// Apply `@nullable`, a null value needs the type, e.g. `@nullable("string")`.
+    ///
+    /// Emit `nullable: true` for openapi 3.0 and `type: [T, "null"]` for 3.1, a `$ref` is
+    /// wrapped in `allOf` or `anyOf` respectively.
+    fn parse_nullable(&self, keys: &Keys, value: &Node, schema: &mut Schema) -> OpenapiResult<()> {
+        let nullable_keys = keys.join(KeyOrIndex::annotation("@nullable"));
+        let null_type = match value.get(&KeyOrIndex::annotation("@nullable")) {
+            None => return Ok(()),
+            Some(Node::Bool(nullable)) if !nullable.value() => return Ok(()),
+            Some(Node::String(schema_type)) => Some(
+                serde_json::from_value::<SchemaType>(Value::String(
+                    schema_type.value().to_string(),
+                ))
+                .ok()
+                .filter(|v| !matches!(v, SchemaType::Null))
+                .ok_or_else(|| OpenapiError::new(nullable_keys.clone(), "is invalid type"))?,
+            ),
+            Some(Node::Null(_) | Node::Bool(_)) => None,
+            Some(_) => return Err(OpenapiError::new(nullable_keys, "must be bool or string")),
+        };
+        match null_type {
+            Some(_) if !value.is_null() => {
+                return Err(OpenapiError::new(
+                    nullable_keys,
+                    "type is only supported on null value",
+                ))
+            }
+            Some(null_type) => schema.schema_type = Some(null_type.into()),
+            None if value.is_null() => {
+                return Err(OpenapiError::new(
+                    nullable_keys,
+                    "must have type on null value",
+                ))
+            }
+            None => {}
+        }
+        if let Some(enum_value) = schema.enum_value.as_mut() {
+            if !enum_value.contains(&Value::Null) {
+                enum_value.push(Value::Null);
+            }
+        }
+        let null_schema = || Schema {
+            schema_type: Some(SchemaType::Null.into()),
+            ..Default::default()
+        };
+        if self.openapi.is_v3_1() {
+            if schema.ref_value.is_some() {
+                let ref_schema = std::mem::take(schema);
+                schema.any_of = Some(vec![ref_schema, null_schema()]);
+            } else if schema.schema_type.is_some() && !schema.maybe_type(&SchemaType::Null) {
+                let mut types = match schema.one_type() {
+                    Some(one_type) => vec![one_type],
+                    None => {
+                        let mut types: Vec<SchemaType> = schema.types().into_iter().collect();
+                        types.sort_by_key(|v| v.to_string());
+                        types
+                    }
+                };
+                types.push(SchemaType::Null);
+                schema.schema_type = Some(OneOrMultiTypes::new(types.into_iter()));
+            } else if schema.schema_type.is_none() {
+                if let Some(schemas) = schema.one_of.as_mut().or(schema.any_of.as_mut()) {
+                    schemas.push(null_schema());
+                }
+            }
+        } else {
+            if schema.ref_value.is_some() {
+                let ref_schema = std::mem::take(schema);
+                schema.all_of = Some(vec![ref_schema]);
+            }
+            schema
+                .unknown
+                .get_or_insert_with(Default::default)
+                .insert("nullable".into(), Value::Bool(true));
+        }
+        Ok(())
+    }
+
+    fn refine_schema_node(
+        &mut self,
+        keys: &Keys,
+        value: &Node,
+        schema: &mut Schema,
+        infer_format: bool,
+    ) -> OpenapiResult<()> {
+        let read_only = parse_bool_annotation(keys, value, "@readonly")?;
+        let write_only = parse_bool_annotation(keys, value, "@writeonly")?;
+        if read_only == Some(true) && write_only == Some(true) {
+            return Err(OpenapiError::new(
+                keys.join(KeyOrIndex::annotation("@writeonly")),
+                "is conflict with @readonly",
+            ));
+        }
+        if read_only.is_some() {
+            schema.read_only = read_only;
+        }
+        if write_only.is_some() {
+            schema.write_only = write_only;
+        }
+        if self.parse_enum(keys, value, schema)? {
+            return Ok(());
+        }
+        let infer_format = infer_format && schema.format.is_none();
+        match value {
+            Node::String(value) if infer_format && schema.maybe_type(&SchemaType::String) => {
+                schema.format = format::infer_string_format(value.value()).map(Into::into);
+            }
+            Node::Number(_) if infer_format && schema.maybe_type(&SchemaType::Integer) => {
+                schema.format = value
+                    .to_plain_json()
+                    .as_i64()
+                    .and_then(format::infer_integer_format)
+                    .map(Into::into);
+            }
+            Node::Object(object) => {
+                for (key, child) in object.value().read().iter() {
+                    let child_schema = match parse_string_annotation(keys, child, "@pattern")? {
+                        Some(pattern) => schema
+                            .pattern_properties
+                            .as_mut()
+                            .and_then(|v| v.get_mut(&pattern)),
+                        None => schema
+                            .properties
+                            .as_mut()
+                            .and_then(|v| v.get_mut(key.value())),
+                    };
+                    if let Some(child_schema) = child_schema {
+                        let keys = keys.join(key.clone());
+                        self.refine_schema(&keys, child, child_schema, infer_format)?;
+                    }
+                }
+            }
+            Node::Array(array) => {
+                let schemas = match (
+                    schema.one_of.as_mut(),
+                    schema.any_of.as_mut(),
+                    schema.all_of.as_mut(),
+                    schema.items.as_mut(),
+                ) {
+                    (Some(schemas), _, _, _)
+                    | (_, Some(schemas), _, _)
+                    | (_, _, Some(schemas), _) => schemas.iter_mut().collect(),
+                    (_, _, _, Some(items)) => match items.value.as_mut().left() {
+                        Some(item) => vec![item.as_mut()],
+                        None => items.value.as_mut().right().unwrap().iter_mut().collect(),
+                    },
+                    _ => vec![],
+                };
+                for (index, (child, child_schema)) in
+                    array.value().read().iter().zip(schemas).enumerate()
+                {
+                    self.refine_schema(&keys.join(index), child, child_schema, infer_format)?;
+                }
+            }
+            _ => {}
+        }
+        self.parse_extends(keys, value, schema)?;
+        self.parse_discriminator(keys, value, schema)
+    }
+
+    /// Apply `@extends("Base")` or `@extends(["Base", ...])` on an object, either as `allOf`
+    /// or with the properties of bases merged in.
+    fn parse_extends(
+        &mut self,
+        keys: &Keys,
+        value: &Node,
+        schema: &mut Schema,
+    ) -> OpenapiResult<()> {
+        let extends_keys = keys.join(KeyOrIndex::annotation("@extends"));
+        let bases: Vec<(Keys, String)> = match value.get(&KeyOrIndex::annotation("@extends")) {
+            None => return Ok(()),
+            Some(Node::String(base)) => vec![(extends_keys.clone(), base.value().to_string())],
+            Some(Node::Array(bases)) => bases
+                .value()
+                .read()
+                .iter()
+                .enumerate()
+                .map(|(index, base)| match base {
+                    Node::String(base) => Ok((extends_keys.join(index), base.value().to_string())),
+                    _ => Err(OpenapiError::new(
+                        extends_keys.join(index),
+                        "must be string",
+                    )),
+                })
+                .collect::<OpenapiResult<_>>()?,
+            Some(_) => return Err(OpenapiError::new(extends_keys, "must be string or array")),
+        };
+        if !value.is_object() {
+            return Err(OpenapiError::new(
+                extends_keys,
+                "is only supported on object",
+            ));
+        }
+        if self.options.flatten_extends {
+            // Bases are merged by `parse_deferred` once all defs are known.
+            self.defer(extends_keys, schema, DeferredKind::Extends(bases));
+            return Ok(());
+        }
+        let mut own = std::mem::take(schema);
+        schema.description = own.description.take();
+        let mut all_of: Vec<Schema> = bases
+            .iter()
+            .map(|(_, base)| Schema {
+                ref_value: Some(format!("{SCHEMA_REF_PREFIX}{base}")),
+                ..Default::default()
+            })
+            .collect();
+        if own.properties.is_some() || own.pattern_properties.is_some() {
+            all_of.push(own);
+        }
+        schema.all_of = Some(all_of);
+        self.deferred.push(Deferred {
+            keys: extends_keys,
+            file_location: None,
+            kind: DeferredKind::Extends(bases),
+        });
+        Ok(())
+    }
+
+    /// Record an item resolved by `parse_deferred` and mark the schema it applies to.
+    fn defer(&mut self, keys: Keys, schema: &mut Schema, kind: DeferredKind) {
+        schema.unknown.get_or_insert_with(Default::default).insert(
+            DEFERRED_KEY.into(),
+            format!("{}{}", self.deferred_marker, self.deferred.len()).into(),
+        );
+        self.deferred.push(Deferred {
+            keys,
+            file_location: None,
+            kind,
+        });
+    }
+
+    /// Resolve `@extends` bases and `@discriminator` values against the defs collected from
+    /// all files, then apply them to the marked schemas.
+    fn parse_deferred(&mut self) {
+        let deferred = std::mem::take(&mut self.deferred);
+        if deferred.is_empty() {
+            return;
+        }
+        let mut context = DeferredContext::default();
+        for (index, item) in deferred.iter().enumerate() {
+            let result = match &item.kind {
+                DeferredKind::Extends(bases) => self.check_extends(bases),
+                DeferredKind::Discriminator {
+                    property,
+                    variants,
+                    mapping,
+                } => self
+                    .resolve_discriminator(property, variants, mapping)
+                    .map(|v| {
+                        context.discriminators.insert(index, v);
+                    }),
+            };
+            if let Err(error) = result {
+                self.errors.push(item.locate(error));
+            }
+        }
+        if !self.errors.is_empty() {
+            return;
+        }
+        let names: Vec<String> = self.defs.borrow().keys().cloned().collect();
+        for name in names {
+            self.resolve_deferred_def(&deferred, &mut context, &name);
+        }
+        let mut value = serde_json::to_value(&self.openapi).unwrap();
+        if self.apply_deferred(&deferred, &mut context, &mut value) {
+            self.openapi = serde_json::from_value(value).unwrap();
+        }
+    }
+
+    fn check_extends(&self, bases: &[(Keys, String)]) -> OpenapiResult<()> {
+        let defs = self.defs.borrow();
+        for (keys, base) in bases {
+            if !defs.contains_key(base) {
+                return Err(OpenapiError::new(
+                    keys.clone(),
+                    format!("unknown def {base}"),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply the deferred items inside a def, after the defs it extends.
+    fn resolve_deferred_def(
+        &mut self,
+        deferred: &[Deferred],
+        context: &mut DeferredContext,
+        name: &str,
+    ) {
+        if context.resolved.contains(name) {
+            return;
+        }
+        let schema = match self.defs.borrow().get(name) {
+            Some(schema) => schema.clone(),
+            None => return,
+        };
+        context.resolving.push(name.to_string());
+        let mut value = serde_json::to_value(&schema).unwrap();
+        if self.apply_deferred(deferred, context, &mut value) {
+            self.defs
+                .borrow_mut()
+                .insert(name.to_string(), serde_json::from_value(value).unwrap());
+        }
+        context.resolving.pop();
+        context.resolved.insert(name.to_string());
+    }
+
+    fn apply_deferred(
+        &mut self,
+        deferred: &[Deferred],
+        context: &mut DeferredContext,
+        value: &mut Value,
+    ) -> bool {
+        let mut changed = false;
+        match value {
+            Value::Object(object) => {
+                for value in object.values_mut() {
+                    changed |= self.apply_deferred(deferred, context, value);
+                }
+                let index = match object
+                    .get(DEFERRED_KEY)
+                    .and_then(|v| v.as_str())
+                    .and_then(|v| v.strip_prefix(&self.deferred_marker))
+                {
+                    Some(index) => index.to_string(),
+                    None => return changed,
+                };
+                object.remove(DEFERRED_KEY);
+                let (index, item) = match index
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|index| Some((index, deferred.get(index)?)))
+                {
+                    Some(v) => v,
+                    None => {
+                        self.errors.push(OpenapiError::new(
+                            Keys::default(),
+                            format!("{DEFERRED_KEY} {index} is invalid"),
+                        ));
+                        return true;
+                    }
+                };
+                match &item.kind {
+                    DeferredKind::Discriminator { .. } => {
+                        if let Some(discriminator) = context.discriminators.get(&index) {
+                            object.insert("discriminator".into(), discriminator.clone());
+                        }
+                    }
+                    DeferredKind::Extends(bases) => {
+                        let mut schema: Schema =
+                            serde_json::from_value(Value::Object(std::mem::take(object))).unwrap();
+                        if let Err(error) =
+                            self.flatten_extends(deferred, context, bases, &mut schema)
+                        {
+                            self.errors.push(item.locate(error));
+                        }
+                        if let Value::Object(value) = serde_json::to_value(schema).unwrap() {
+                            *object = value;
+                        }
+                    }
+                }
+                true
+            }
+            Value::Array(array) => {
+                for value in array {
+                    changed |= self.apply_deferred(deferred, context, value);
+                }
+                changed
+            }
+            _ => false,
+        }
+    }
+
+    /// Merge the properties of bases into the schema, own properties win.
+    fn flatten_extends(
+        &mut self,
+        deferred: &[Deferred],
+        context: &mut DeferredContext,
+        bases: &[(Keys, String)],
+        schema: &mut Schema,
+    ) -> OpenapiResult<()> {
+        let own_properties = schema.properties.take().unwrap_or_default();
+        let own_required = schema.required.take().unwrap_or_default();
+        let mut properties: IndexMap<String, Schema> = IndexMap::default();
+        let mut required: Vec<String> = vec![];
+        for (keys, base) in bases {
+            let base_schema = self.extends_base(deferred, context, keys, base)?;
+            for (name, property) in base_schema.properties.iter().flatten() {
+                properties.insert(name.clone(), property.clone());
+            }
+            for name in base_schema.required.iter().flatten() {
+                if !required.contains(name) {
+                    required.push(name.clone());
+                }
+            }
+        }
+        required.retain(|v| !own_properties.contains_key(v));
+        properties.extend(own_properties);
+        required.extend(own_required);
+        schema.properties = Some(properties).filter(|v| !v.is_empty());
+        schema.required = Some(required).filter(|v| !v.is_empty());
+        Ok(())
+    }
+
+    /// The resolved object schema of a base, following refs between defs.
+    fn extends_base(
+        &mut self,
+        deferred: &[Deferred],
+        context: &mut DeferredContext,
+        keys: &Keys,
+        base: &str,
+    ) -> OpenapiResult<Schema> {
+        let mut name = base.to_string();
+        let mut seen: Vec<String> = vec![];
+        loop {
+            if context.resolving.contains(&name) {
+                return Err(OpenapiError::new(
+                    keys.clone(),
+                    format!("{name} is circular"),
+                ));
+            }
+            self.resolve_deferred_def(deferred, context, &name);
+            let schema = self.defs.borrow().get(&name).cloned();
+            let target = schema.as_ref().and_then(|v| match v.ref_value.as_ref() {
+                Some(ref_value) if v.properties.is_none() => {
+                    ref_value.strip_prefix(SCHEMA_REF_PREFIX)
+                }
+                _ => None,
+            });
+            match (target, schema.as_ref()) {
+                (Some(target), _) if !seen.iter().any(|v| v == target) => {
+                    seen.push(name);
+                    name = target.to_string();
+                }
+                (None, Some(schema))
+                    if schema.ref_value.is_none()
+                        && schema.all_of.is_none()
+                        && schema.one_of.is_none()
+                        && schema.any_of.is_none()
+                        && schema.maybe_type(&SchemaType::Object) =>
+                {
+                    return Ok(schema.clone());
+                }
+                _ => {
+                    return Err(OpenapiError::new(
+                        keys.clone(),
+                        format!("{base} is not an object"),
+                    ))
+                }
+            }
+        }
+    }
+
+    /// The discriminator object with the mapping of every variant.
+    fn resolve_discriminator(
+        &self,
+        property: &str,
+        variants: &[DiscriminatorVariant],
+        mapping: &[(Keys, String, String)],
+    ) -> OpenapiResult<Value> {
+        let defs = self.defs.borrow();
+        let mut result = IndexMap::new();
+        for variant in variants {
+            match variant {
+                DiscriminatorVariant::Inline { literal, def } => {
+                    result.insert(literal.clone(), format!("{SCHEMA_REF_PREFIX}{def}"));
+                }
+                DiscriminatorVariant::Def { keys, def } => {
+                    let literal = match self.def_nodes.get(def) {
+                        Some(node) => match node.get(&KeyOrIndex::property(property)) {
+                            Some(Node::String(v)) if !v.value().is_empty() => {
+                                Some(v.value().to_string())
+                            }
+                            _ => None,
+                        },
+                        // Without the jsona value, use the single enum value of the property.
+                        None => defs
+                            .get(def)
+                            .and_then(|v| v.properties.as_ref())
+                            .and_then(|v| v.get(property))
+                            .and_then(|v| v.enum_value.as_ref())
+                            .and_then(|v| match v.as_slice() {
+                                [Value::String(v)] => Some(v.clone()),
+                                _ => None,
+                            }),
+                    };
+                    match literal {
+                        Some(literal) => {
+                            result.insert(literal, format!("{SCHEMA_REF_PREFIX}{def}"));
+                        }
+                        None if mapping.iter().any(|(_, _, v)| v == def) => {}
+                        None => {
+                            return Err(OpenapiError::new(
+                                keys.clone(),
+                                format!("must have string property {property}"),
+                            ))
+                        }
+                    }
+                }
+            }
+        }
+        for (keys, literal, def) in mapping {
+            if !defs.contains_key(def) {
+                return Err(OpenapiError::new(
+                    keys.clone(),
+                    format!("unknown def {def}"),
+                ));
+            }
+            result.insert(literal.clone(), format!("{SCHEMA_REF_PREFIX}{def}"));
+        }
+        let mut discriminator = serde_json::json!({ "propertyName": property });
+        if !result.is_empty() {
+            discriminator["mapping"] = serde_json::to_value(result).unwrap();
+        }
+        Ok(discriminator)
+    }
+
+    /// Apply `@enum([...])` on a scalar value, or a bare `@enum` on an array whose items
+    /// are the allowed values of its items. Return true if the node is an enum.
+    fn parse_enum(
+        &mut self,
+        keys: &Keys,
+        value: &Node,
+        schema: &mut Schema,
+    ) -> OpenapiResult<bool> {
+        let enum_keys = keys.join(KeyOrIndex::annotation("@enum"));
+        let (values, values_keys) = match (value.get(&KeyOrIndex::annotation("@enum")), value) {
+            (None, _) => return Ok(false),
+            (Some(Node::Null(_)), Node::Array(array)) => (array.clone(), keys.clone()),
+            (Some(Node::Null(_)), _) => {
+                return Err(OpenapiError::new(
+                    enum_keys,
+                    "must have values unless used on array",
+                ))
+            }
+            (Some(_), Node::Array(_) | Node::Object(_)) => {
+                return Err(OpenapiError::new(
+                    enum_keys,
+                    "is only supported on string, number or bool",
+                ))
+            }
+            (Some(Node::Array(values)), _) => (values.clone(), enum_keys.clone()),
+            (Some(_), _) => return Err(OpenapiError::new(enum_keys, "must be array")),
+        };
+        let values = values.value().read();
+        if values.is_empty() {
+            return Err(OpenapiError::new(values_keys, "must not be empty"));
+        }
+        let mut enum_type = None;
+        for (index, item) in values.iter().enumerate() {
+            let item_type = match SchemaType::from_node(item) {
+                Some(
+                    v @ (SchemaType::String
+                    | SchemaType::Number
+                    | SchemaType::Integer
+                    | SchemaType::Boolean),
+                ) => v,
+                _ => {
+                    return Err(OpenapiError::new(
+                        values_keys.join(index),
+                        "must be string, number or bool",
+                    ))
+                }
+            };
+            enum_type = match merge_enum_type(enum_type, item_type) {
+                Some(v) => Some(v),
+                None => {
+                    return Err(OpenapiError::new(
+                        values_keys.join(index),
+                        "is mismatched with other values",
+                    ))
+                }
+            };
+        }
+        let mut enum_type = enum_type.unwrap();
+        let enum_value: Vec<Value> = values.iter().map(|v| v.to_plain_json()).collect();
+        if !value.is_array() {
+            enum_type = SchemaType::from_node(value)
+                .and_then(|v| merge_enum_type(Some(enum_type), v))
+                .ok_or_else(|| OpenapiError::new(enum_keys, "is mismatched with value type"))?;
+            let example = value.to_plain_json();
+            if !enum_value.contains(&example) {
+                self.warnings.push(OpenapiError::new(
+                    keys.clone(),
+                    format!("{example} is not one of the @enum values"),
+                ));
+            }
+        }
+        let enum_schema = Schema {
+            schema_type: Some(enum_type.into()),
+            enum_value: Some(enum_value),
+            ..Default::default()
+        };
+        if value.is_array() {
+            schema.items = Some(OneOrMultiSchemas::new(vec![enum_schema]));
+        } else {
+            schema.schema_type = enum_schema.schema_type;
+            schema.enum_value = enum_schema.enum_value;
+        }
+        Ok(true)
+    }
+
+    /// Apply `@discriminator` on an array whose items are the variants of `oneOf`, unless
+    /// `@compound` says otherwise. Inline variants are moved into `components.schemas`.
+    fn parse_discriminator(
+        &mut self,
+        keys: &Keys,
+        value: &Node,
+        schema: &mut Schema,
+    ) -> OpenapiResult<()> {
+        let discriminator_keys = keys.join(KeyOrIndex::annotation("@discriminator"));
+        let options = match value.get(&KeyOrIndex::annotation("@discriminator")) {
+            None => return Ok(()),
+            Some(Node::String(name)) => DiscriminatorOptions {
+                property_name: name.value().to_string(),
+                mapping: None,
+            },
+            Some(Node::Object(_)) => {
+                parse_object_annotation::<DiscriminatorOptions>(keys, value, "@discriminator")?
+            }
+            Some(_) => {
+                return Err(OpenapiError::new(
+                    discriminator_keys,
+                    "must be string or object",
+                ))
+            }
+        };
+        let array = match value {
+            Node::Array(array) => array,
+            _ => {
+                return Err(OpenapiError::new(
+                    discriminator_keys,
+                    "is only supported on array",
+                ))
+            }
+        };
+        if schema.all_of.is_some() {
+            return Err(OpenapiError::new(
+                discriminator_keys,
+                "is not supported with allOf",
+            ));
+        }
+        if schema.one_of.is_none() && schema.any_of.is_none() {
+            let items = schema
+                .items
+                .take()
+                .ok_or_else(|| OpenapiError::new(keys.clone(), "must not be empty"))?;
+            schema.one_of = Some(items.value.either(|v| vec![*v], |v| v));
+            schema.schema_type = None;
+        }
+        let variants = match schema.one_of.as_mut() {
+            Some(variants) => variants,
+            None => schema.any_of.as_mut().unwrap(),
+        };
+        let property = options.property_name;
+        let mut discriminator_variants = vec![];
+        for (index, (item, variant)) in array
+            .value()
+            .read()
+            .iter()
+            .zip(variants.iter_mut())
+            .enumerate()
+        {
+            if let Some(def) = variant
+                .ref_value
+                .as_ref()
+                .and_then(|v| v.strip_prefix(SCHEMA_REF_PREFIX))
+            {
+                // The value of a def is looked up once all defs are known.
+                discriminator_variants.push(DiscriminatorVariant::Def {
+                    keys: keys.join(index),
+                    def: def.to_string(),
+                });
+                continue;
+            }
+            let literal = match item.get(&KeyOrIndex::property(property.clone())) {
+                Some(Node::String(v)) if !v.value().is_empty() => v.value().to_string(),
+                _ => {
+                    return Err(OpenapiError::new(
+                        keys.join(index),
+                        format!("must have string property {property}"),
+                    ))
+                }
+            };
+            let def = naming::to_pascal_case(&naming::split_words(&literal));
+            if self.defs.borrow().contains_key(&def) {
+                return Err(OpenapiError::new(
+                    keys.join(index),
+                    format!("variant {def} is conflict, use @def to name it"),
+                ));
+            }
+            let ref_schema = Schema {
+                ref_value: Some(format!("{SCHEMA_REF_PREFIX}{def}")),
+                ..Default::default()
+            };
+            let variant = std::mem::replace(variant, ref_schema);
+            self.defs.borrow_mut().insert(def.clone(), variant);
+            self.def_nodes.insert(def.clone(), item.clone());
+            discriminator_variants.push(DiscriminatorVariant::Inline { literal, def });
+        }
+        let mapping = options
+            .mapping
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(literal, def)| {
+                let keys = discriminator_keys
+                    .join(KeyOrIndex::property("mapping"))
+                    .join(KeyOrIndex::property(literal.clone()));
+                (keys, literal, def)
+            })
+            .collect();
+        self.defer(
+            discriminator_keys,
+            schema,
+            DeferredKind::Discriminator {
+                property,
+                variants: discriminator_variants,
+                mapping,
+            },
+        );
+        Ok(())
+    }
+
     fn def_parameters(
         &mut self,
         name: String,
@@ -552,12 +2920,219 @@ impl OpenapiParser {
             .as_mut()
             .unwrap()
             .insert(name.clone(), value);
-        return Ok(ObjectOrReference::Ref {
-            ref_path: format!("#/components/parameters/{}", name),
-        });
+        Ok(ObjectOrReference::Ref {
+            ref_path: format!("{PARAMETER_REF_PREFIX}{name}"),
+        })
     }
 }
 
+/// Defaults shared by the endpoints nested in a `@group`.
+#[derive(Clone, Debug, Default)]
+struct Group {
+    prefix: String,
+    pathname: String,
+    tags: Option<Vec<String>>,
+    security: Option<Vec<SecurityRequirement>>,
+    parameters: Vec<ObjectOrReference<Parameter>>,
+    responses: IndexMap<String, Response>,
+}
+
+impl Group {
+    fn apply(&self, operation: &mut Operation) {
+        if operation.tags.is_none() {
+            operation.tags = self.tags.clone();
+        }
+        if operation.security.is_none() {
+            operation.security = self.security.clone();
+        }
+        if !self.parameters.is_empty() {
+            let mut parameters: Vec<ObjectOrReference<Parameter>> = self
+                .parameters
+                .iter()
+                .filter(|v| match v {
+                    ObjectOrReference::Object(v) => {
+                        !operation.parameters.iter().flatten().any(|p| match p {
+                            ObjectOrReference::Object(p) => {
+                                p.name == v.name && p.location == v.location
+                            }
+                            ObjectOrReference::Ref { .. } => false,
+                        })
+                    }
+                    ObjectOrReference::Ref { .. } => true,
+                })
+                .cloned()
+                .collect();
+            parameters.extend(operation.parameters.take().unwrap_or_default());
+            operation.parameters = Some(parameters);
+        }
+        for (status, response) in self.responses.iter() {
+            if !operation.responses.contains_key(status) {
+                operation.responses.insert(status.clone(), response.clone());
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GroupOptions {
+    prefix: Option<String>,
+    tags: Option<Vec<String>>,
+    security: Option<Vec<SecurityRequirement>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PathOptions {
+    summary: Option<String>,
+    description: Option<String>,
+    servers: Option<Vec<Server>>,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Checkpoint {
+    errors: usize,
+    warnings: usize,
+    links: usize,
+    external_refs: usize,
+    generic_refs: usize,
+    deferred: usize,
+}
+
+/// A `@ref` pointing into another file.
+#[derive(Debug)]
+struct ExternalRef {
+    keys: Keys,
+    file_location: Option<FileLocation>,
+    file: PathBuf,
+    fragment: String,
+    kind: RefKind,
+}
+
+/// The component an external ref points to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum RefKind {
+    Schema,
+    Parameter,
+}
+
+impl RefKind {
+    fn prefix(&self) -> &'static str {
+        match self {
+            RefKind::Schema => SCHEMA_REF_PREFIX,
+            RefKind::Parameter => PARAMETER_REF_PREFIX,
+        }
+    }
+
+    fn components_key(&self) -> &'static str {
+        match self {
+            RefKind::Schema => "schemas",
+            RefKind::Parameter => "parameters",
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            RefKind::Schema => "schema",
+            RefKind::Parameter => "parameter",
+        }
+    }
+}
+
+/// A generic def declared with `@def("Page<T>")`.
+#[derive(Debug)]
+struct Template {
+    /// The declared def, e.g. `Page<T>`.
+    def: String,
+    params: Vec<String>,
+}
+
+/// The declarations collected from all files before parsing endpoints.
+#[derive(Debug, Clone, Copy)]
+enum Declaration {
+    Defs,
+    Paths,
+}
+
+/// An `@extends` or `@discriminator` which refers to defs that may be declared later.
+#[derive(Debug)]
+struct Deferred {
+    keys: Keys,
+    file_location: Option<FileLocation>,
+    kind: DeferredKind,
+}
+
+impl Deferred {
+    fn locate(&self, mut error: OpenapiError) -> OpenapiError {
+        if error.location.is_none() {
+            error.location = self.file_location.clone().map(Box::new);
+        }
+        error
+    }
+}
+
+#[derive(Debug)]
+enum DeferredKind {
+    /// Bases checked to exist, and merged into the marked schema when flattening.
+    Extends(Vec<(Keys, String)>),
+    /// Variants and explicit `(keys, value, def)` mapping of a marked union.
+    Discriminator {
+        property: String,
+        variants: Vec<DiscriminatorVariant>,
+        mapping: Vec<(Keys, String, String)>,
+    },
+}
+
+#[derive(Debug)]
+enum DiscriminatorVariant {
+    /// An inline variant moved into defs, its value is known from the jsona value.
+    Inline { literal: String, def: String },
+    /// A `@ref` variant.
+    Def { keys: Keys, def: String },
+}
+
+#[derive(Debug, Default)]
+struct DeferredContext {
+    discriminators: HashMap<usize, Value>,
+    resolved: HashSet<String>,
+    resolving: Vec<String>,
+}
+
+/// A `@ref` instantiating a generic def, e.g. `Page<Pet>`.
+#[derive(Clone, Debug)]
+struct GenericRef {
+    keys: Keys,
+    file_location: Option<FileLocation>,
+}
+
+/// A `@link` waiting for its target endpoint to be parsed.
+#[derive(Debug)]
+struct PendingLink {
+    keys: Keys,
+    location: Option<(String, MethodKind)>,
+    file_location: Option<FileLocation>,
+    status: String,
+    name: String,
+    options: LinkOptions,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct DiscriminatorOptions {
+    property_name: String,
+    mapping: Option<IndexMap<String, String>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct LinkOptions {
+    endpoint: String,
+    parameters: Option<IndexMap<String, Value>>,
+    request_body: Option<Value>,
+    description: Option<String>,
+    server: Option<Server>,
+}
+
 fn get_components_mut(spec: &mut Openapi) -> &mut Components {
     if spec.components.is_none() {
         spec.components = Some(Default::default());
@@ -565,6 +3140,265 @@ fn get_components_mut(spec: &mut Openapi) -> &mut Components {
     spec.components.as_mut().unwrap()
 }
 
+fn include_paths(keys: &Keys, node: &Node) -> (Vec<String>, Vec<OpenapiError>) {
+    let keys = match node.get_as_string("@include") {
+        Some((key, _)) => keys.join(key),
+        None => return (vec![], vec![]),
+    };
+    match node.get(&KeyOrIndex::annotation("@include")) {
+        Some(Node::String(path)) => (vec![path.value().to_string()], vec![]),
+        Some(Node::Array(paths)) => {
+            let paths = paths.value().read();
+            let mut result = vec![];
+            let mut errors = vec![];
+            for (index, path) in paths.iter().enumerate() {
+                match path.as_string() {
+                    Some(path) => result.push(path.value().to_string()),
+                    None => errors.push(OpenapiError::new(
+                        keys.join(KeyOrIndex::Index(index)),
+                        "must be string",
+                    )),
+                }
+            }
+            (result, errors)
+        }
+        Some(_) => (
+            vec![],
+            vec![OpenapiError::new(keys, "must be string or array")],
+        ),
+        None => (vec![], vec![]),
+    }
+}
+
+/// The prefix and pathname of a group, which is all `@path` declarations need of it. Invalid
+/// groups are reported by `parse_group`.
+fn path_group(keys: &Keys, value: &Node, parent: &Group) -> Option<Group> {
+    let options: GroupOptions = parse_object_annotation(keys, value, "@group").ok()?;
+    let prefix = options.prefix.unwrap_or_default();
+    let names: Vec<String> = match value
+        .get_as_object("req")
+        .and_then(|(_, v)| v)
+        .and_then(|v| Node::from(v).get_as_object("params"))
+    {
+        Some((_, Some(params))) => params
+            .value()
+            .read()
+            .iter()
+            .map(|(k, _)| k.value().to_string())
+            .collect(),
+        _ => vec![],
+    };
+    let mut names = names.into_iter();
+    let pathname: Vec<String> = prefix
+        .split('/')
+        .map(|part| match part {
+            "{}" => names
+                .next()
+                .map(|name| format!("{{{name}}}"))
+                .unwrap_or_else(|| part.to_string()),
+            _ => part.to_string(),
+        })
+        .collect();
+    Some(Group {
+        prefix: format!("{}{}", parent.prefix, prefix),
+        pathname: format!("{}{}", parent.pathname, pathname.join("/")),
+        ..Default::default()
+    })
+}
+
+fn load_file(file: &Path, source: &str) -> Result<(Node, Mapper), Vec<OpenapiError>> {
+    let mapper = Mapper::new_utf16(source, false);
+    match source.parse::<Node>() {
+        Ok(node) => Ok((node, mapper)),
+        Err(error) => Err(error
+            .to_error_objects(&mapper)
+            .into_iter()
+            .map(|v| OpenapiError::new(Keys::default(), v.message).with_file(file, v.range))
+            .collect()),
+    }
+}
+
+fn is_jsona_file(file: &Path) -> bool {
+    file.extension().map(|v| v == "jsona").unwrap_or_default()
+}
+
+/// Convert the fragment of an external ref to json pointer, a jsona fragment is a def name.
+fn external_pointer(file: &Path, fragment: &str, kind: RefKind) -> String {
+    if fragment.is_empty() || fragment.starts_with('/') {
+        fragment.to_string()
+    } else if is_jsona_file(file) {
+        format!("/components/{}/{fragment}", kind.components_key())
+    } else {
+        format!("/{fragment}")
+    }
+}
+
+fn collect_refs(value: &Value) -> Vec<String> {
+    let mut refs = vec![];
+    let mut stack = vec![value];
+    while let Some(value) = stack.pop() {
+        match value {
+            Value::Object(object) => {
+                for (key, value) in object {
+                    match value {
+                        Value::String(ref_value) if key == "$ref" => {
+                            if !refs.contains(ref_value) {
+                                refs.push(ref_value.clone());
+                            }
+                        }
+                        _ => stack.push(value),
+                    }
+                }
+            }
+            Value::Array(array) => stack.extend(array),
+            _ => {}
+        }
+    }
+    refs
+}
+
+/// Replace `$ref` values found in refs, returns whether anything is replaced.
+fn rewrite_refs(value: &mut Value, refs: &HashMap<String, String>) -> bool {
+    let mut changed = false;
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                match value {
+                    Value::String(ref_value) if key == "$ref" => {
+                        if let Some(target) = refs.get(ref_value.as_str()) {
+                            *ref_value = target.clone();
+                            changed = true;
+                        }
+                    }
+                    _ => changed |= rewrite_refs(value, refs),
+                }
+            }
+        }
+        Value::Array(array) => {
+            for value in array {
+                changed |= rewrite_refs(value, refs);
+            }
+        }
+        _ => {}
+    }
+    changed
+}
+
+/// Split a generic def like `Page<Pet>` or `Map<String, Page<Pet>>` into its name and arguments.
+fn parse_generic(value: &str) -> Option<(String, Vec<String>)> {
+    let (name, rest) = value.split_once('<')?;
+    let rest = rest.strip_suffix('>')?;
+    let name = name.trim();
+    if !is_identifier(name) {
+        return None;
+    }
+    let mut args = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (index, c) in rest.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' if depth == 0 => return None,
+            '>' => depth -= 1,
+            ',' if depth == 0 => {
+                args.push(rest[start..index].trim().to_string());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    if depth != 0 {
+        return None;
+    }
+    args.push(rest[start..].trim().to_string());
+    if args.iter().any(|v| v.is_empty()) {
+        return None;
+    }
+    Some((name.to_string(), args))
+}
+
+/// Replace the type parameters in a def name, e.g. `List<T>` => `List<Pet>`.
+fn substitute_generic(value: &str, substitutions: &HashMap<&str, &str>) -> String {
+    let mut output = String::new();
+    let mut word = String::new();
+    for c in value.chars().chain(std::iter::once('\0')) {
+        if c.is_alphanumeric() || c == '_' {
+            word.push(c);
+            continue;
+        }
+        let word = std::mem::take(&mut word);
+        output.push_str(substitutions.get(word.as_str()).copied().unwrap_or(&word));
+        if c != '\0' {
+            output.push(c);
+        }
+    }
+    output
+}
+
+/// Name of a generic instance, e.g. `Page<Pet>` => `PagePet`.
+fn generic_instance_name(value: &str) -> String {
+    value
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|v| !v.is_empty())
+        .map(naming::upper_first)
+        .collect()
+}
+
+fn is_identifier(value: &str) -> bool {
+    !value.is_empty() && value.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Merge the types of enum values, integers widen to number.
+fn merge_enum_type(current: Option<SchemaType>, other: SchemaType) -> Option<SchemaType> {
+    match (current, other) {
+        (None, other) => Some(other),
+        (Some(current), other) if current == other => Some(current),
+        (Some(SchemaType::Integer), SchemaType::Number)
+        | (Some(SchemaType::Number), SchemaType::Integer) => Some(SchemaType::Number),
+        _ => None,
+    }
+}
+
+/// Name the values of a string enum, or of the items of an array of string enum, for code
+/// generators. Values with the same name, e.g. `in-stock` and `in_stock`, get a number suffix.
+fn add_enum_varnames(schema: &mut Schema) {
+    if let Some(item) = schema.items.as_mut().and_then(|v| v.value.as_mut().left()) {
+        add_enum_varnames(item);
+    }
+    let values = match (&schema.enum_value, schema.one_type()) {
+        (Some(values), Some(SchemaType::String)) => values,
+        _ => return,
+    };
+    let mut varnames: Vec<String> = vec![];
+    for value in values {
+        let name = naming::to_pascal_case(&naming::split_words(value.as_str().unwrap_or_default()));
+        let name = if name.is_empty() {
+            "Empty".to_string()
+        } else if name.starts_with(|c: char| c.is_ascii_digit()) {
+            format!("Value{name}")
+        } else {
+            name
+        };
+        let name = if varnames.contains(&name) {
+            (2..)
+                .map(|index| format!("{name}{index}"))
+                .find(|v| !varnames.contains(v))
+                .unwrap()
+        } else {
+            name
+        };
+        varnames.push(name);
+    }
+    schema
+        .unknown
+        .get_or_insert_with(Default::default)
+        .insert("x-enum-varnames".into(), varnames.into());
+}
+
+fn canonicalize(file: &Path) -> PathBuf {
+    std::fs::canonicalize(file).unwrap_or_else(|_| file.to_path_buf())
+}
+
 fn exist_annotation(value: &Node, name: &str) -> bool {
     value.get(&KeyOrIndex::annotation(name)).is_some()
 }
@@ -577,19 +3411,73 @@ fn parse_string_annotation(keys: &Keys, value: &Node, name: &str) -> OpenapiResu
     }
 }
 
-fn parse_ref_annotation<T>(
+fn parse_object_annotation<T: DeserializeOwned + Default>(
     keys: &Keys,
     value: &Node,
-    ref_prefix: &str,
-) -> OpenapiResult<Option<ObjectOrReference<T>>> {
-    match parse_string_annotation(keys, value, "@ref")? {
-        Some(ref_value) => Ok(Some(ObjectOrReference::Ref {
-            ref_path: format!("{}{}", ref_prefix, ref_value),
-        })),
+    name: &str,
+) -> OpenapiResult<T> {
+    match value.get(&KeyOrIndex::annotation(name)) {
+        Some(Node::Null(_)) | None => Ok(T::default()),
+        Some(Node::Object(value)) => serde_json::from_value(Node::from(value).to_plain_json())
+            .map_err(|error| {
+                OpenapiError::new(
+                    keys.join(KeyOrIndex::annotation(name)),
+                    format!("invalid {} value, {error}", &name[1..]),
+                )
+            }),
+        Some(_) => Err(OpenapiError::new(
+            keys.join(KeyOrIndex::annotation(name)),
+            "must be object",
+        )),
+    }
+}
+
+fn parse_extension_annotation(keys: &Keys, value: &Node) -> OpenapiResult<Extensions> {
+    match value.get_as_object("@extension") {
+        Some((key, Some(object))) => {
+            let mut extensions = IndexMap::default();
+            for (name, value) in object.value().read().iter() {
+                if !name.value().starts_with("x-") {
+                    return Err(OpenapiError::new(
+                        keys.join(key).join(name.clone()),
+                        "must begin with x-",
+                    ));
+                }
+                extensions.insert(name.value().to_string(), value.to_plain_json());
+            }
+            Ok(Extensions(extensions))
+        }
+        Some((key, None)) => Err(OpenapiError::new(keys.join(key), "must be object")),
+        None => Ok(Extensions::default()),
+    }
+}
+
+fn parse_bool_annotation(keys: &Keys, value: &Node, name: &str) -> OpenapiResult<Option<bool>> {
+    match value.get(&KeyOrIndex::annotation(name)) {
+        Some(Node::Null(_)) => Ok(Some(true)),
+        Some(Node::Bool(value)) => Ok(Some(value.value())),
+        Some(_) => Err(OpenapiError::new(
+            keys.join(KeyOrIndex::annotation(name)),
+            "must be bool",
+        )),
         None => Ok(None),
     }
 }
 
+fn parse_security_requirement(keys: &Keys, value: &Node) -> OpenapiResult<SecurityRequirement> {
+    match value {
+        Node::String(name) => {
+            let mut requirement = SecurityRequirement::default();
+            requirement.insert(name.value().to_string(), vec![]);
+            Ok(requirement)
+        }
+        Node::Object(_) => serde_json::from_value(value.to_plain_json())
+            .map_err(|error| OpenapiError::new(keys.clone(), format!("is invalid, {error}"))),
+        _ => Err(OpenapiError::new(keys.clone(), "must be string or object")),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum MethodKind {
     Get,
     Post,
@@ -618,6 +3506,24 @@ impl MethodKind {
             MethodKind::Patch => path_item.patch = Some(operation),
         };
     }
+    pub fn operation<'a>(&self, path_item: &'a PathItem) -> Option<&'a Operation> {
+        match self {
+            MethodKind::Get => path_item.get.as_ref(),
+            MethodKind::Post => path_item.post.as_ref(),
+            MethodKind::Put => path_item.put.as_ref(),
+            MethodKind::Delete => path_item.delete.as_ref(),
+            MethodKind::Patch => path_item.patch.as_ref(),
+        }
+    }
+    pub fn operation_mut<'a>(&self, path_item: &'a mut PathItem) -> Option<&'a mut Operation> {
+        match self {
+            MethodKind::Get => path_item.get.as_mut(),
+            MethodKind::Post => path_item.post.as_mut(),
+            MethodKind::Put => path_item.put.as_mut(),
+            MethodKind::Delete => path_item.delete.as_mut(),
+            MethodKind::Patch => path_item.patch.as_mut(),
+        }
+    }
 }
 impl Display for MethodKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
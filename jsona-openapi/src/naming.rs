@@ -0,0 +1,58 @@
+/// Split an identifier into words on separators and case boundaries.
+pub(crate) fn split_words(value: &str) -> Vec<String> {
+    let chars: Vec<char> = value.chars().collect();
+    let mut words = vec![];
+    let mut word = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+        if c.is_uppercase() && !word.is_empty() {
+            let prev = chars[i - 1];
+            let next_lowercase = chars.get(i + 1).map(|v| v.is_lowercase()).unwrap_or(false);
+            if prev.is_lowercase() || prev.is_numeric() || (prev.is_uppercase() && next_lowercase) {
+                words.push(std::mem::take(&mut word));
+            }
+        }
+        word.push(c);
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+pub(crate) fn to_pascal_case<S: AsRef<str>>(words: &[S]) -> String {
+    words
+        .iter()
+        .map(|v| upper_first(&v.as_ref().to_lowercase()))
+        .collect()
+}
+
+pub(crate) fn to_camel_case<S: AsRef<str>>(words: &[S]) -> String {
+    let value = to_pascal_case(words);
+    let mut chars = value.chars();
+    match chars.next() {
+        Some(c) => c.to_lowercase().chain(chars).collect(),
+        None => value,
+    }
+}
+
+pub(crate) fn to_snake_case<S: AsRef<str>>(words: &[S]) -> String {
+    words
+        .iter()
+        .map(|v| v.as_ref().to_lowercase())
+        .collect::<Vec<String>>()
+        .join("_")
+}
+
+pub(crate) fn upper_first(value: &str) -> String {
+    let mut chars = value.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
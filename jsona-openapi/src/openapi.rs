@@ -1,6 +1,8 @@
+use std::ops::{Deref, DerefMut};
+
 use indexmap::IndexMap;
 use jsona_schema::Schema;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
 /// top level document
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Default)]
@@ -49,9 +51,25 @@ pub struct Openapi {
     /// in order to construct the full URL. The Paths MAY be empty, due to
     /// [ACL constraints](https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.1.md#securityFiltering).
     pub paths: IndexMap<String, PathItem>,
+    /// The incoming webhooks that MAY be received as part of this API and that the API
+    /// consumer MAY choose to implement. Only available since OpenAPI 3.1.
+    ///
+    /// See <https://spec.openapis.org/oas/v3.1.0#oasWebhooks>.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub webhooks: Option<IndexMap<String, ObjectOrReference<PathItem>>>,
     /// An element to hold various schemas for the specification.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub components: Option<Components>,
+    /// Specification extensions, the keys MUST begin with `x-`.
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+impl Openapi {
+    /// Whether the document targets OpenAPI 3.1.
+    pub fn is_v3_1(&self) -> bool {
+        self.openapi.starts_with("3.1")
+    }
 }
 
 /// General information about the API.
@@ -79,6 +97,9 @@ pub struct Info {
     /// The license information for the exposed API.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub license: Option<License>,
+    /// Specification extensions, the keys MUST begin with `x-`.
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
 /// Wraper around `url::Url` to fix serde issue
@@ -91,6 +112,12 @@ impl Url {
     }
 }
 
+impl From<url::Url> for Url {
+    fn from(value: url::Url) -> Self {
+        Url(value)
+    }
+}
+
 /// Contact information for the exposed API.
 ///
 /// See <https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.1.md#contactObject>.
@@ -105,6 +132,9 @@ pub struct Contact {
     // TODO: Make sure the email is a valid email
     #[serde(skip_serializing_if = "Option::is_none")]
     pub email: Option<String>,
+    /// Specification extensions, the keys MUST begin with `x-`.
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
 /// License information for the exposed API.
@@ -117,6 +147,9 @@ pub struct License {
     /// A URL to the license used for the API.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub url: Option<Url>,
+    /// Specification extensions, the keys MUST begin with `x-`.
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
 /// An object representing a Server.
@@ -136,6 +169,9 @@ pub struct Server {
     /// the server's URL template.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub variables: Option<IndexMap<String, ServerVariable>>,
+    /// Specification extensions, the keys MUST begin with `x-`.
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
 /// An object representing a Server Variable for server URL template substitution.
@@ -156,6 +192,9 @@ pub struct ServerVariable {
     /// [CommonMark]: https://spec.commonmark.org/
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    /// Specification extensions, the keys MUST begin with `x-`.
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
 /// Holds a set of reusable objects for different aspects of the OAS.
@@ -201,6 +240,9 @@ pub struct Components {
     /// An object to hold reusable Callback Objects.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub callbacks: Option<IndexMap<String, ObjectOrReference<Callback>>>,
+    /// Specification extensions, the keys MUST begin with `x-`.
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
 /// Describes the operations available on a single path.
@@ -270,6 +312,9 @@ pub struct PathItem {
     /// [OpenAPI Object's components/parameters](https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.1.md#componentsParameters).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parameters: Option<Vec<ObjectOrReference<Parameter>>>,
+    /// Specification extensions, the keys MUST begin with `x-`.
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
 /// Describes a single API operation on a path.
@@ -298,8 +343,9 @@ pub struct Operation {
     #[serde(skip_serializing_if = "Option::is_none", rename = "operationId")]
     pub operation_id: Option<String>,
 
-    #[serde(flatten, skip_serializing_if = "Option::is_none")]
-    pub extensions: Option<IndexMap<String, serde_json::Value>>,
+    /// Specification extensions, the keys MUST begin with `x-`.
+    #[serde(flatten)]
+    pub extensions: Extensions,
 
     /// A list of parameters that are applicable for this operation. If a parameter is already
     /// defined at the
@@ -380,6 +426,9 @@ pub struct ExternalDoc {
     /// [CommonMark syntax](http://spec.commonmark.org/) MAY be used for rich text representation.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    /// Specification extensions, the keys MUST begin with `x-`.
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
 // FIXME: Verify against OpenAPI 3.0
@@ -435,6 +484,9 @@ pub struct Parameter {
     /// Example of the parameter type.
     #[serde(flatten, skip_serializing_if = "Option::is_none")]
     pub examples: Option<OneOrMultiExample>,
+    /// Specification extensions, the keys MUST begin with `x-`.
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
@@ -449,6 +501,20 @@ pub enum ParameterStyle {
     DeepObject,
 }
 
+impl ParameterStyle {
+    /// Whether the style can serialize a parameter in the given location.
+    pub fn is_allowed_in(&self, location: &str) -> bool {
+        match self {
+            ParameterStyle::Label | ParameterStyle::Matrix => location == "path",
+            ParameterStyle::Form => matches!(location, "query" | "cookie"),
+            ParameterStyle::Simple => matches!(location, "path" | "header"),
+            ParameterStyle::SpaceDelimited
+            | ParameterStyle::PipeDelimited
+            | ParameterStyle::DeepObject => location == "query",
+        }
+    }
+}
+
 /// Describes a single request body.
 ///
 /// See <https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.1.md#requestBodyObject>.
@@ -467,6 +533,9 @@ pub struct RequestBody {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub required: Option<bool>,
+    /// Specification extensions, the keys MUST begin with `x-`.
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
 /// Each Media Type Object provides schema and examples for the media type identified by its key.
@@ -488,6 +557,9 @@ pub struct MediaType {
     /// or `application/x-www-form-urlencoded`.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub encoding: Option<IndexMap<String, Encoding>>,
+    /// Specification extensions, the keys MUST begin with `x-`.
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
 /// A single encoding definition applied to a single schema property.
@@ -536,6 +608,9 @@ pub struct Encoding {
     /// not `application/x-www-form-urlencoded`.
     #[serde(skip_serializing_if = "Option::is_none", rename = "allowReserved")]
     pub allow_reserved: Option<bool>,
+    /// Specification extensions, the keys MUST begin with `x-`.
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
 /// Describes a single response from an API Operation, including design-time, static `links`
@@ -567,6 +642,9 @@ pub struct Response {
     /// [Component Objects](https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.1.md#componentsObject).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub links: Option<IndexMap<String, ObjectOrReference<Link>>>,
+    /// Specification extensions, the keys MUST begin with `x-`.
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
 /// A map of possible out-of band callbacks related to the parent operation. Each value in
@@ -578,8 +656,9 @@ pub struct Response {
 /// See <https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.1.md#callbackObject>.
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Default)]
 pub struct Callback(
-    /// A Path Item Object used to define a callback request and expected responses.
-    serde_json::Value,
+    /// Path Item Objects used to define callback requests and expected responses,
+    /// keyed by the runtime expression of the callback URL.
+    pub IndexMap<String, PathItem>,
 );
 
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
@@ -610,20 +689,28 @@ pub struct Example {
     /// [CommonMark syntax](http://spec.commonmark.org/) MAY be used for rich text representation.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
-    // FIXME: Implement (merge with externalValue as enum)
-    /// Embedded literal example. The `value` field and `externalValue` field are mutually
-    /// exclusive. To represent examples of media types that cannot naturally represented
-    /// in JSON or YAML, use a string value to contain the example, escaping where necessary.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub value: Option<serde_json::Value>,
-    // FIXME: Implement (merge with value as enum)
-    // /// A URL that points to the literal example. This provides the capability to reference
-    // /// examples that cannot easily be included in JSON or YAML documents. The `value` field
-    // /// and `externalValue` field are mutually exclusive.
-    // #[serde(skip_serializing_if = "Option::is_none")]
-    // pub externalValue: Option<String>,
+    /// The example value, either embedded or pointed by an URL.
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub value: Option<ExampleValue>,
+    /// Specification extensions, the keys MUST begin with `x-`.
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
 
-    // TODO: Add "Specification Extensions" https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.1.md#specificationExtensions}
+/// The `value` field and `externalValue` field of an example are mutually exclusive.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(untagged)]
+pub enum ExampleValue {
+    /// A URL that points to the literal example. This provides the capability to reference
+    /// examples that cannot easily be included in JSON or YAML documents.
+    External {
+        #[serde(rename = "externalValue")]
+        external_value: String,
+    },
+    /// Embedded literal example. To represent examples of media types that cannot naturally
+    /// represented in JSON or YAML, use a string value to contain the example, escaping where
+    /// necessary.
+    Value { value: serde_json::Value },
 }
 
 /// The Link object represents a possible design-time link for a response.
@@ -654,24 +741,22 @@ pub enum Link {
         #[serde(rename = "operationRef")]
         operation_ref: String,
 
-        // FIXME: Implement
-        // /// A map representing parameters to pass to an operation as specified with `operationId`
-        // /// or identified via `operationRef`. The key is the parameter name to be used, whereas
-        // /// the value can be a constant or an expression to be evaluated and passed to the
-        // /// linked operation. The parameter name can be qualified using the
-        // /// [parameter location](https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.1.md#parameterIn)
-        // /// `[{in}.]{name}` for operations that use the same parameter name in different
-        // /// locations (e.g. path.id).
-        // parameters: IndexMap<String, Any | {expression}>,
+        /// A map representing parameters to pass to an operation as specified with `operationId`
+        /// or identified via `operationRef`. The key is the parameter name to be used, whereas
+        /// the value can be a constant or an expression to be evaluated and passed to the
+        /// linked operation. The parameter name can be qualified using the
+        /// [parameter location](https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.1.md#parameterIn)
+        /// `[{in}.]{name}` for operations that use the same parameter name in different
+        /// locations (e.g. path.id).
         #[serde(skip_serializing_if = "Option::is_none")]
-        parameters: Option<IndexMap<String, String>>,
-
-        // FIXME: Implement
-        // /// A literal value or
-        // /// [{expression}](https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.1.md#runtimeExpression)
-        // /// to use as a request body when calling the target operation.
-        // #[serde(rename = "requestBody")]
-        // request_body: Any | {expression}
+        parameters: Option<IndexMap<String, serde_json::Value>>,
+
+        /// A literal value or
+        /// [{expression}](https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.1.md#runtimeExpression)
+        /// to use as a request body when calling the target operation.
+        #[serde(rename = "requestBody", skip_serializing_if = "Option::is_none")]
+        request_body: Option<serde_json::Value>,
+
         /// A description of the link. [CommonMark syntax](http://spec.commonmark.org/) MAY be
         /// used for rich text representation.
         #[serde(skip_serializing_if = "Option::is_none")]
@@ -680,7 +765,10 @@ pub enum Link {
         /// A server object to be used by the target operation.
         #[serde(skip_serializing_if = "Option::is_none")]
         server: Option<Server>,
-        // TODO: Add "Specification Extensions" https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.1.md#specificationExtension
+
+        /// Specification extensions, the keys MUST begin with `x-`.
+        #[serde(flatten)]
+        extensions: Extensions,
     },
     /// The name of an _existing_, resolvable OAS operation, as defined with a unique
     /// `operationId`. This field is mutually exclusive of the `operationRef` field.
@@ -688,24 +776,22 @@ pub enum Link {
         #[serde(rename = "operationId")]
         operation_id: String,
 
-        // FIXME: Implement
-        // /// A map representing parameters to pass to an operation as specified with `operationId`
-        // /// or identified via `operationRef`. The key is the parameter name to be used, whereas
-        // /// the value can be a constant or an expression to be evaluated and passed to the
-        // /// linked operation. The parameter name can be qualified using the
-        // /// [parameter location](https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.1.md#parameterIn)
-        // /// `[{in}.]{name}` for operations that use the same parameter name in different
-        // /// locations (e.g. path.id).
-        // parameters: IndexMap<String, Any | {expression}>,
+        /// A map representing parameters to pass to an operation as specified with `operationId`
+        /// or identified via `operationRef`. The key is the parameter name to be used, whereas
+        /// the value can be a constant or an expression to be evaluated and passed to the
+        /// linked operation. The parameter name can be qualified using the
+        /// [parameter location](https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.1.md#parameterIn)
+        /// `[{in}.]{name}` for operations that use the same parameter name in different
+        /// locations (e.g. path.id).
         #[serde(skip_serializing_if = "Option::is_none")]
-        parameters: Option<IndexMap<String, String>>,
-
-        // FIXME: Implement
-        // /// A literal value or
-        // /// [{expression}](https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.1.md#runtimeExpression)
-        // /// to use as a request body when calling the target operation.
-        // #[serde(rename = "requestBody")]
-        // request_body: Any | {expression}
+        parameters: Option<IndexMap<String, serde_json::Value>>,
+
+        /// A literal value or
+        /// [{expression}](https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.1.md#runtimeExpression)
+        /// to use as a request body when calling the target operation.
+        #[serde(rename = "requestBody", skip_serializing_if = "Option::is_none")]
+        request_body: Option<serde_json::Value>,
+
         /// A description of the link. [CommonMark syntax](http://spec.commonmark.org/) MAY be
         /// used for rich text representation.
         #[serde(skip_serializing_if = "Option::is_none")]
@@ -714,7 +800,10 @@ pub enum Link {
         /// A server object to be used by the target operation.
         #[serde(skip_serializing_if = "Option::is_none")]
         server: Option<Server>,
-        // TODO: Add "Specification Extensions" https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.1.md#specificationExtension
+
+        /// Specification extensions, the keys MUST begin with `x-`.
+        #[serde(flatten)]
+        extensions: Extensions,
     },
 }
 
@@ -767,6 +856,9 @@ pub struct Header {
     /// Example of the parameter type.
     #[serde(flatten, skip_serializing_if = "Option::is_none")]
     pub examples: Option<OneOrMultiExample>,
+    /// Specification extensions, the keys MUST begin with `x-`.
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
 /// Adds metadata to a single tag that is used by the
@@ -785,6 +877,9 @@ pub struct Tag {
     /// Additional external documentation for this tag.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub external_docs: Option<Vec<ExternalDoc>>,
+    /// Specification extensions, the keys MUST begin with `x-`.
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
 /// A simple object to allow referencing other components in the specification, internally and externally.
@@ -808,31 +903,86 @@ pub enum ObjectOrReference<T> {
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 #[serde(tag = "type")]
 pub enum SecurityScheme {
+    /// API key passed as a header, query parameter or cookie.
     #[serde(rename = "apiKey")]
     ApiKey {
+        /// The name of the header, query or cookie parameter to be used.
         name: String,
+        /// The location of the API key.
         #[serde(rename = "in")]
-        location: String,
+        location: ApiKeyLocation,
+        /// A short description for security scheme.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        /// Specification extensions, the keys MUST begin with `x-`.
+        #[serde(flatten)]
+        extensions: Extensions,
     },
+    /// HTTP authentication as defined in [RFC7235](https://tools.ietf.org/html/rfc7235#section-5.1).
     #[serde(rename = "http")]
     Http {
+        /// The name of the HTTP Authorization scheme, e.g. `basic` or `bearer`.
         scheme: String,
-        #[serde(rename = "bearerFormat")]
-        bearer_format: String,
+        /// A hint to the client to identify how the bearer token is formatted.
+        #[serde(rename = "bearerFormat", skip_serializing_if = "Option::is_none")]
+        bearer_format: Option<String>,
+        /// A short description for security scheme.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        /// Specification extensions, the keys MUST begin with `x-`.
+        #[serde(flatten)]
+        extensions: Extensions,
     },
+    /// OAuth2 with one or more flows.
     #[serde(rename = "oauth2")]
-    OAuth2 { flows: Box<Flows> },
+    OAuth2 {
+        /// Configuration information for the flow types supported.
+        flows: Box<Flows>,
+        /// A short description for security scheme.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        /// Specification extensions, the keys MUST begin with `x-`.
+        #[serde(flatten)]
+        extensions: Extensions,
+    },
+    /// OpenID Connect Discovery.
     #[serde(rename = "openIdConnect")]
     OpenIdConnect {
+        /// OpenId Connect URL to discover OAuth2 configuration values.
         #[serde(rename = "openIdConnectUrl")]
         open_id_connect_url: String,
+        /// A short description for security scheme.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        /// Specification extensions, the keys MUST begin with `x-`.
+        #[serde(flatten)]
+        extensions: Extensions,
+    },
+    /// Mutual TLS, added in OpenAPI 3.1.
+    #[serde(rename = "mutualTLS")]
+    MutualTls {
+        /// A short description for security scheme.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        /// Specification extensions, the keys MUST begin with `x-`.
+        #[serde(flatten)]
+        extensions: Extensions,
     },
 }
 
+/// The location of an API key.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiKeyLocation {
+    Query,
+    Header,
+    Cookie,
+}
+
 /// Allows configuration of the supported OAuth Flows.
-/// See [link]
-/// [link][https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.1.md#oauth-flows-object]
-#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+///
+/// See <https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.1.md#oauthFlowsObject>.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct Flows {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -843,55 +993,98 @@ pub struct Flows {
     pub client_credentials: Option<ClientCredentialsFlow>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub authorization_code: Option<AuthorizationCodeFlow>,
+    /// Specification extensions, the keys MUST begin with `x-`.
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+impl Flows {
+    /// All scopes declared by any of the flows.
+    pub fn scopes(&self) -> impl Iterator<Item = &String> {
+        [
+            self.implicit.as_ref().map(|v| &v.scopes),
+            self.password.as_ref().map(|v| &v.scopes),
+            self.client_credentials.as_ref().map(|v| &v.scopes),
+            self.authorization_code.as_ref().map(|v| &v.scopes),
+        ]
+        .into_iter()
+        .flatten()
+        .flat_map(|v| v.keys())
+    }
 }
 
-/// Configuration details for a implicit OAuth Flow
-/// See [link]
-/// [link](https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.1.md#oauth-flow-object)
+/// Configuration details for a implicit OAuth Flow.
+///
+/// See <https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.1.md#oauthFlowObject>.
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct ImplicitFlow {
+    /// The authorization URL to be used for this flow.
     pub authorization_url: Url,
+    /// The URL to be used for obtaining refresh tokens.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub refresh_url: Option<Url>,
+    /// The available scopes for the OAuth2 security scheme.
     pub scopes: IndexMap<String, String>,
+    /// Specification extensions, the keys MUST begin with `x-`.
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
-/// Configuration details for a password OAuth Flow
-/// See [link]
-/// [link](https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.1.md#oauth-flow-object
+/// Configuration details for a password OAuth Flow.
+///
+/// See <https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.1.md#oauthFlowObject>.
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct PasswordFlow {
-    token_url: Url,
+    /// The token URL to be used for this flow.
+    pub token_url: Url,
+    /// The URL to be used for obtaining refresh tokens.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub refresh_url: Option<Url>,
+    /// The available scopes for the OAuth2 security scheme.
     pub scopes: IndexMap<String, String>,
+    /// Specification extensions, the keys MUST begin with `x-`.
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
-/// Configuration details for a client credentials OAuth Flow
-/// See [link]
-/// [link](https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.1.md#oauth-flow-object
+/// Configuration details for a client credentials OAuth Flow.
+///
+/// See <https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.1.md#oauthFlowObject>.
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct ClientCredentialsFlow {
-    token_url: Url,
+    /// The token URL to be used for this flow.
+    pub token_url: Url,
+    /// The URL to be used for obtaining refresh tokens.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub refresh_url: Option<Url>,
+    /// The available scopes for the OAuth2 security scheme.
     pub scopes: IndexMap<String, String>,
+    /// Specification extensions, the keys MUST begin with `x-`.
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
-/// Configuration details for a authorization code OAuth Flow
-/// See [link]
-/// [link](https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.1.md#oauth-flow-object
+/// Configuration details for a authorization code OAuth Flow.
+///
+/// See <https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.1.md#oauthFlowObject>.
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct AuthorizationCodeFlow {
+    /// The authorization URL to be used for this flow.
     pub authorization_url: Url,
-    token_url: Url,
+    /// The token URL to be used for this flow.
+    pub token_url: Url,
+    /// The URL to be used for obtaining refresh tokens.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub refresh_url: Option<Url>,
+    /// The available scopes for the OAuth2 security scheme.
     pub scopes: IndexMap<String, String>,
+    /// Specification extensions, the keys MUST begin with `x-`.
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
 /// Lists the required security schemes to execute this operation. The name used for each property MUST correspond to a security scheme declared in the Security Schemes under the Components Object.
@@ -902,3 +1095,39 @@ pub struct AuthorizationCodeFlow {
 ///
 /// See <https://github.com/OAI/OpenAPI-Specification/blob/master/versions/3.0.1.md#securityRequirementObject>.
 pub type SecurityRequirement = IndexMap<String, Vec<String>>;
+
+/// Specification extensions, the keys MUST begin with `x-`.
+///
+/// Flattened into the object that owns them, other unknown keys are dropped when deserializing.
+/// Schemas keep their `x-` keys in [`Schema::unknown`](jsona_schema::Schema).
+#[derive(Clone, Debug, Default, Serialize, PartialEq)]
+#[serde(transparent)]
+pub struct Extensions(pub IndexMap<String, serde_json::Value>);
+
+impl<'de> Deserialize<'de> for Extensions {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let map: IndexMap<String, serde_json::Value> = IndexMap::deserialize(deserializer)?;
+        Ok(Extensions(
+            map.into_iter()
+                .filter(|(key, _)| key.starts_with("x-"))
+                .collect(),
+        ))
+    }
+}
+
+impl Deref for Extensions {
+    type Target = IndexMap<String, serde_json::Value>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Extensions {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
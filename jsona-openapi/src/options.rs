@@ -0,0 +1,60 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// Options to control how the openapi document is generated.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OpenapiOptions {
+    /// How to derive `operationId` from an endpoint.
+    pub operation_id: OperationIdStrategy,
+    /// Prefix the derived `operationId` with the first tag of the operation.
+    pub operation_id_tag_prefix: bool,
+    /// Directory to resolve relative file paths against, defaults to the current directory.
+    pub base_dir: Option<PathBuf>,
+    /// Inline json files referenced by `@exampleFile` as example `value`.
+    pub bundle_example_files: bool,
+    /// How to output `@ref` pointing into another file, e.g. `common.jsona#Error`.
+    pub external_refs: ExternalRefMode,
+    /// Replace structurally identical inline object schemas with a ref to a single
+    /// `components.schemas` entry, see [`crate::Openapi::hoist_schemas`].
+    pub dedupe_schemas: bool,
+    /// Name every inline object schema in request and response bodies and move it into
+    /// `components.schemas`, see [`crate::Openapi::name_inline_schemas`].
+    pub name_inline_schemas: bool,
+    /// Infer `format` of string and integer schemas from their values, e.g. `date-time`,
+    /// `uuid`, `email` or `int64`. Use `@inferFormat(false)` to skip a node and its children.
+    pub infer_formats: bool,
+    /// Merge the properties of `@extends` bases into the schema instead of emitting `allOf`.
+    pub flatten_extends: bool,
+    /// Generate request and response variants of component schemas with `@readonly` or
+    /// `@writeonly` properties, see [`crate::Openapi::split_read_write_schemas`].
+    pub split_read_write: bool,
+}
+
+/// Strategy for deriving `operationId` when `@endpoint` does not set one explicitly.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum OperationIdStrategy {
+    /// Use the jsona key as is.
+    #[default]
+    Verbatim,
+    /// Convert the jsona key to camelCase.
+    CamelCase,
+    /// Convert the jsona key to snake_case.
+    SnakeCase,
+    /// Derive from http method and path, e.g. `GET /pet/{petId}` => `getPetByPetId`.
+    MethodPath,
+}
+
+/// Output mode for `@ref` pointing into another file.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ExternalRefMode {
+    /// Keep references into json files as external `$ref`, defs of jsona files are bundled
+    /// as openapi tools can't resolve them.
+    #[default]
+    Keep,
+    /// Copy the referenced schemas into `components.schemas`, renaming them on name collision.
+    Bundle,
+}
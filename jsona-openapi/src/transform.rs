@@ -0,0 +1,590 @@
+use std::collections::{HashMap, HashSet};
+
+use indexmap::IndexMap;
+use serde_json::{Map, Value};
+
+use crate::{naming, Openapi, SCHEMA_REF_PREFIX};
+
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+impl Openapi {
+    /// Inline every `$ref` pointing into this document.
+    ///
+    /// Recursive refs are left in place, so `components` is kept for them to resolve.
+    pub fn dereference(&mut self) {
+        let mut value = serde_json::to_value(&*self).unwrap();
+        let document = value.clone();
+        dereference_value(&mut value, &document, &mut vec![]);
+        *self = serde_json::from_value(value).unwrap();
+    }
+
+    /// Move inline object schemas which occur more than once into `components.schemas`,
+    /// the names are generated from the operation and field path where the schemas occur first.
+    ///
+    /// Schemas are compared structurally, the order of properties and `required` is ignored.
+    /// An inline schema equal to an existing component schema is replaced by a ref to it.
+    pub fn hoist_schemas(&mut self) {
+        let mut value = serde_json::to_value(&*self).unwrap();
+        while hoist_schemas_once(&mut value) {}
+        *self = serde_json::from_value(value).unwrap();
+    }
+
+    /// Move every inline object schema in request and response bodies into
+    /// `components.schemas`, named from the operation and field path, e.g. `AddPetRequestCategory`.
+    /// Object schemas nested in component schemas are named from the component, e.g. `PetCategory`.
+    ///
+    /// An inline schema equal to an existing component schema is replaced by a ref to it,
+    /// so explicit `@def` names always win.
+    pub fn name_inline_schemas(&mut self) {
+        let mut value = serde_json::to_value(&*self).unwrap();
+        let mut namer = SchemaNamer::new(&value);
+        if let Some(schemas) = value
+            .pointer_mut("/components/schemas")
+            .and_then(|v| v.as_object_mut())
+        {
+            for (name, schema) in schemas.iter_mut() {
+                walk_schema_children(schema, name, &mut |schema, hint| namer.visit(schema, hint));
+            }
+        }
+        walk_document(&mut value, true, &mut |schema, hint| {
+            namer.visit(schema, hint)
+        });
+        let mut index = 0;
+        while index < namer.hoisted.len() {
+            let (name, mut schema) = namer.hoisted[index].clone();
+            walk_schema_children(&mut schema, &name, &mut |schema, hint| {
+                namer.visit(schema, hint)
+            });
+            namer.hoisted[index].1 = schema;
+            index += 1;
+        }
+        insert_schemas(&mut value, namer.hoisted);
+        *self = serde_json::from_value(value).unwrap();
+    }
+
+    /// Generate `{Name}Request` and `{Name}Response` variants of component schemas with
+    /// `readOnly` or `writeOnly` properties, dropping the properties which do not apply to
+    /// the variant from `properties` and `required`. Request and response bodies refer to
+    /// the variants, the original schemas are kept.
+    pub fn split_read_write_schemas(&mut self) {
+        let mut value = serde_json::to_value(&*self).unwrap();
+        let schemas = value
+            .pointer("/components/schemas")
+            .and_then(|v| v.as_object())
+            .cloned()
+            .unwrap_or_default();
+        let request = SplitVariant::new(&schemas, "readOnly", "Request");
+        let response = SplitVariant::new(&schemas, "writeOnly", "Response");
+        let mut variants = vec![];
+        for (name, schema) in schemas.iter() {
+            for variant in [&request, &response] {
+                if let Some(variant_name) = variant.names.get(name) {
+                    let mut schema = schema.clone();
+                    variant.strip(&mut schema);
+                    variants.push((variant_name.clone(), schema));
+                }
+            }
+        }
+        // Bodies are visited with the hint `{Operation}Request` or `{Operation}Response`.
+        walk_document(&mut value, true, &mut |schema, hint| {
+            if hint.ends_with("Request") {
+                request.strip(schema);
+            } else {
+                response.strip(schema);
+            }
+            false
+        });
+        insert_schemas(&mut value, variants);
+        *self = serde_json::from_value(value).unwrap();
+    }
+}
+
+/// Strip properties marked with `flag` from schemas, component schemas containing such
+/// properties, directly or through refs, are replaced by their variants.
+struct SplitVariant {
+    flag: &'static str,
+    names: IndexMap<String, String>,
+}
+
+impl SplitVariant {
+    fn new(schemas: &Map<String, Value>, flag: &'static str, suffix: &str) -> Self {
+        let mut affected: HashSet<String> = HashSet::default();
+        loop {
+            let count = affected.len();
+            for (name, schema) in schemas.iter() {
+                if !affected.contains(name) && contains_flag(schema, flag, &affected) {
+                    affected.insert(name.clone());
+                }
+            }
+            if affected.len() == count {
+                break;
+            }
+        }
+        let mut names: IndexMap<String, String> = IndexMap::default();
+        for name in schemas.keys().filter(|v| affected.contains(*v)) {
+            let variant_name = unique_name(&format!("{name}{suffix}"), |v| {
+                schemas.contains_key(v) || names.values().any(|name| name == v)
+            });
+            names.insert(name.clone(), variant_name);
+        }
+        Self { flag, names }
+    }
+
+    fn strip(&self, schema: &mut Value) {
+        let object = match schema.as_object_mut() {
+            Some(v) => v,
+            None => return,
+        };
+        if let Some(Value::String(ref_value)) = object.get_mut("$ref") {
+            if let Some(name) = ref_value
+                .strip_prefix(SCHEMA_REF_PREFIX)
+                .and_then(|v| self.names.get(v))
+            {
+                *ref_value = format!("{SCHEMA_REF_PREFIX}{name}");
+            }
+            return;
+        }
+        let mut removed = vec![];
+        if let Some(Value::Object(properties)) = object.get_mut("properties") {
+            properties.retain(|name, value| {
+                let flagged = value.get(self.flag) == Some(&Value::Bool(true));
+                if flagged {
+                    removed.push(Value::String(name.clone()));
+                }
+                !flagged
+            });
+        }
+        if let Some(Value::Array(required)) = object.get_mut("required") {
+            required.retain(|v| !removed.contains(v));
+            if required.is_empty() {
+                object.remove("required");
+            }
+        }
+        for (key, value) in object.iter_mut() {
+            match (key.as_str(), value) {
+                ("properties" | "patternProperties", Value::Object(properties)) => {
+                    properties.values_mut().for_each(|v| self.strip(v));
+                }
+                ("items" | "allOf" | "anyOf" | "oneOf", Value::Array(schemas)) => {
+                    schemas.iter_mut().for_each(|v| self.strip(v));
+                }
+                ("items" | "additionalProperties" | "not", value) => self.strip(value),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Whether the schema has `flag` set somewhere or refers to an affected schema.
+fn contains_flag(value: &Value, flag: &str, affected: &HashSet<String>) -> bool {
+    match value {
+        Value::Object(object) => object.iter().any(|(key, value)| match value {
+            Value::Bool(true) if key == flag => true,
+            Value::String(ref_value) if key == "$ref" => ref_value
+                .strip_prefix(SCHEMA_REF_PREFIX)
+                .map(|v| affected.contains(v))
+                .unwrap_or_default(),
+            _ => contains_flag(value, flag, affected),
+        }),
+        Value::Array(array) => array.iter().any(|v| contains_flag(v, flag, affected)),
+        _ => false,
+    }
+}
+
+/// Replace every object schema visited with a ref to a named schema.
+struct SchemaNamer {
+    names: HashMap<String, String>,
+    existing: Map<String, Value>,
+    hoisted: Vec<(String, Value)>,
+}
+
+impl SchemaNamer {
+    fn new(document: &Value) -> Self {
+        let existing = document
+            .pointer("/components/schemas")
+            .and_then(|v| v.as_object())
+            .cloned()
+            .unwrap_or_default();
+        let mut names = HashMap::default();
+        for (name, schema) in existing.iter() {
+            if let Some(key) = hoist_key(schema) {
+                names.entry(key).or_insert_with(|| name.clone());
+            }
+        }
+        Self {
+            names,
+            existing,
+            hoisted: vec![],
+        }
+    }
+
+    fn visit(&mut self, schema: &mut Value, hint: &str) -> bool {
+        let key = match hoist_key(schema) {
+            Some(key) => key,
+            None => return true,
+        };
+        let name = match self.names.get(&key) {
+            Some(name) => name.clone(),
+            None => {
+                let name = unique_name(hint, |v| {
+                    self.existing.contains_key(v) || self.hoisted.iter().any(|(name, _)| name == v)
+                });
+                self.names.insert(key, name.clone());
+                self.hoisted.push((name.clone(), schema.clone()));
+                name
+            }
+        };
+        *schema = ref_schema(&name);
+        false
+    }
+}
+
+fn dereference_value(value: &mut Value, document: &Value, stack: &mut Vec<String>) {
+    match value {
+        Value::Object(object) => {
+            if let Some(Value::String(ref_value)) = object.get("$ref") {
+                let ref_value = ref_value.clone();
+                if stack.contains(&ref_value) {
+                    return;
+                }
+                if let Some(target) = ref_value
+                    .strip_prefix('#')
+                    .and_then(|pointer| document.pointer(pointer))
+                {
+                    let mut target = target.clone();
+                    stack.push(ref_value);
+                    dereference_value(&mut target, document, stack);
+                    stack.pop();
+                    object.remove("$ref");
+                    if let Value::Object(mut target_object) = target {
+                        target_object.extend(std::mem::take(object));
+                        *object = target_object;
+                    } else {
+                        *value = target;
+                    }
+                    return;
+                }
+            }
+            for value in object.values_mut() {
+                dereference_value(value, document, stack);
+            }
+        }
+        Value::Array(array) => {
+            for value in array {
+                dereference_value(value, document, stack);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Hoist the outermost repeated schemas, returns whether anything is hoisted.
+fn hoist_schemas_once(document: &mut Value) -> bool {
+    let mut counts: HashMap<String, usize> = HashMap::default();
+    walk_document(document, false, &mut |schema, _| {
+        if let Some(key) = hoist_key(schema) {
+            *counts.entry(key).or_default() += 1;
+        }
+        true
+    });
+    let mut names: HashMap<String, String> = HashMap::default();
+    let schemas = document
+        .pointer("/components/schemas")
+        .and_then(|v| v.as_object())
+        .cloned()
+        .unwrap_or_default();
+    for (name, schema) in schemas.iter() {
+        if let Some(key) = hoist_key(schema) {
+            names.entry(key).or_insert_with(|| name.clone());
+        }
+    }
+    let mut hoisted: Vec<(String, Value)> = vec![];
+    let mut replaced = false;
+    walk_document(document, false, &mut |schema, hint| {
+        let key = match hoist_key(schema) {
+            Some(key) => key,
+            None => return true,
+        };
+        let name = match names.get(&key) {
+            Some(name) => name.clone(),
+            None if counts.get(&key).copied().unwrap_or_default() > 1 => {
+                let name = unique_name(hint, |v| {
+                    schemas.contains_key(v) || hoisted.iter().any(|(name, _)| name == v)
+                });
+                hoisted.push((name.clone(), schema.clone()));
+                names.insert(key, name.clone());
+                name
+            }
+            None => return true,
+        };
+        *schema = ref_schema(&name);
+        replaced = true;
+        false
+    });
+    insert_schemas(document, hoisted);
+    replaced
+}
+
+fn ref_schema(name: &str) -> Value {
+    let mut object = Map::new();
+    object.insert(
+        "$ref".into(),
+        Value::String(format!("{SCHEMA_REF_PREFIX}{name}")),
+    );
+    Value::Object(object)
+}
+
+fn insert_schemas(document: &mut Value, schemas: Vec<(String, Value)>) {
+    if schemas.is_empty() {
+        return;
+    }
+    let object = document.as_object_mut().unwrap();
+    let components = object
+        .entry("components")
+        .or_insert_with(|| Value::Object(Map::new()));
+    let existing = components
+        .as_object_mut()
+        .unwrap()
+        .entry("schemas")
+        .or_insert_with(|| Value::Object(Map::new()));
+    if let Some(existing) = existing.as_object_mut() {
+        existing.extend(schemas);
+    }
+}
+
+/// Only object schemas with properties are worth to be hoisted.
+fn hoist_key(schema: &Value) -> Option<String> {
+    let object = schema.as_object()?;
+    if object.get("type").and_then(|v| v.as_str()) != Some("object") {
+        return None;
+    }
+    if object
+        .get("properties")
+        .and_then(|v| v.as_object())
+        .map(|v| v.is_empty())
+        .unwrap_or(true)
+    {
+        return None;
+    }
+    serde_json::to_string(&canonicalize(schema)).ok()
+}
+
+fn canonicalize(value: &Value) -> Value {
+    match value {
+        Value::Object(object) => {
+            let mut keys: Vec<&String> = object.keys().collect();
+            keys.sort();
+            let mut result = Map::new();
+            for key in keys {
+                let mut value = canonicalize(&object[key]);
+                if let ("required", Value::Array(items)) = (key.as_str(), &mut value) {
+                    items.sort_by(|a, b| a.as_str().cmp(&b.as_str()));
+                }
+                result.insert(key.clone(), value);
+            }
+            Value::Object(result)
+        }
+        Value::Array(array) => Value::Array(array.iter().map(canonicalize).collect()),
+        _ => value.clone(),
+    }
+}
+
+fn unique_name(hint: &str, exists: impl Fn(&str) -> bool) -> String {
+    let name = if hint.is_empty() { "Schema" } else { hint };
+    if !exists(name) {
+        return name.to_string();
+    }
+    (2..)
+        .map(|index| format!("{name}{index}"))
+        .find(|v| !exists(v))
+        .unwrap()
+}
+
+type SchemaVisitor<'a> = dyn FnMut(&mut Value, &str) -> bool + 'a;
+
+/// Visit schemas in the document with a name hint, descend into the schema if the visitor
+/// returns true. Schemas in `components.schemas` are named already, only their children
+/// are visited, before the schemas in paths so that named context comes first.
+///
+/// With `bodies_only`, only schemas of request and response bodies are visited.
+fn walk_document(document: &mut Value, bodies_only: bool, f: &mut SchemaVisitor) {
+    if let Some(components) = document.get_mut("components").filter(|_| !bodies_only) {
+        if let Some(schemas) = components
+            .get_mut("schemas")
+            .and_then(|v| v.as_object_mut())
+        {
+            for (name, schema) in schemas.iter_mut() {
+                walk_schema_children(schema, name, f);
+            }
+        }
+        if let Some(parameters) = components
+            .get_mut("parameters")
+            .and_then(|v| v.as_object_mut())
+        {
+            for (name, parameter) in parameters.iter_mut() {
+                if let Some(schema) = parameter.get_mut("schema") {
+                    walk_schema(schema, &naming::upper_first(name), f);
+                }
+            }
+        }
+    }
+    if let Some(paths) = document.get_mut("paths").and_then(|v| v.as_object_mut()) {
+        for (path, path_item) in paths.iter_mut() {
+            walk_path_item(path_item, path, bodies_only, f);
+        }
+    }
+    if let Some(webhooks) = document.get_mut("webhooks").and_then(|v| v.as_object_mut()) {
+        for (name, path_item) in webhooks.iter_mut() {
+            walk_path_item(path_item, name, bodies_only, f);
+        }
+    }
+}
+
+fn walk_path_item(path_item: &mut Value, path: &str, bodies_only: bool, f: &mut SchemaVisitor) {
+    let path_item = match path_item.as_object_mut() {
+        Some(v) => v,
+        None => return,
+    };
+    if let Some(parameters) = path_item.get_mut("parameters").filter(|_| !bodies_only) {
+        walk_parameters(parameters, "", f);
+    }
+    for method in METHODS {
+        if let Some(operation) = path_item.get_mut(method) {
+            walk_operation(operation, method, path, bodies_only, f);
+        }
+    }
+}
+
+fn walk_operation(
+    operation: &mut Value,
+    method: &str,
+    path: &str,
+    bodies_only: bool,
+    f: &mut SchemaVisitor,
+) {
+    let name = match operation.get("operationId").and_then(|v| v.as_str()) {
+        Some(operation_id) => naming::to_pascal_case(&naming::split_words(operation_id)),
+        None => {
+            let mut words = vec![method.to_string()];
+            words.extend(naming::split_words(path));
+            naming::to_pascal_case(&words)
+        }
+    };
+    if let Some(parameters) = operation.get_mut("parameters").filter(|_| !bodies_only) {
+        walk_parameters(parameters, &name, f);
+    }
+    if let Some(content) = operation.pointer_mut("/requestBody/content") {
+        walk_content(content, &format!("{name}Request"), f);
+    }
+    if let Some(responses) = operation
+        .get_mut("responses")
+        .and_then(|v| v.as_object_mut())
+    {
+        for (status, response) in responses.iter_mut() {
+            let hint = if status.starts_with('2') {
+                format!("{name}Response")
+            } else {
+                format!("{name}{}Response", naming::upper_first(status))
+            };
+            if let Some(content) = response.get_mut("content") {
+                walk_content(content, &hint, f);
+            }
+            if let Some(headers) = response
+                .get_mut("headers")
+                .and_then(|v| v.as_object_mut())
+                .filter(|_| !bodies_only)
+            {
+                for (header, value) in headers.iter_mut() {
+                    if let Some(schema) = value.get_mut("schema") {
+                        let mut words = naming::split_words(header);
+                        words.push("header".into());
+                        walk_schema(schema, &naming::to_pascal_case(&words), f);
+                    }
+                }
+            }
+        }
+    }
+    if let Some(callbacks) = operation
+        .get_mut("callbacks")
+        .and_then(|v| v.as_object_mut())
+    {
+        for (callback_name, callback) in callbacks.iter_mut() {
+            if let Some(callback) = callback.as_object_mut() {
+                for path_item in callback.values_mut() {
+                    walk_path_item(path_item, callback_name, bodies_only, f);
+                }
+            }
+        }
+    }
+}
+
+fn walk_parameters(parameters: &mut Value, prefix: &str, f: &mut SchemaVisitor) {
+    if let Some(parameters) = parameters.as_array_mut() {
+        for parameter in parameters {
+            let name = parameter
+                .get("name")
+                .and_then(|v| v.as_str())
+                .map(|v| naming::to_pascal_case(&naming::split_words(v)))
+                .unwrap_or_default();
+            if let Some(schema) = parameter.get_mut("schema") {
+                walk_schema(schema, &format!("{prefix}{name}"), f);
+            }
+        }
+    }
+}
+
+fn walk_content(content: &mut Value, hint: &str, f: &mut SchemaVisitor) {
+    if let Some(content) = content.as_object_mut() {
+        for media_type in content.values_mut() {
+            if let Some(schema) = media_type.get_mut("schema") {
+                walk_schema(schema, hint, f);
+            }
+        }
+    }
+}
+
+fn walk_schema(schema: &mut Value, hint: &str, f: &mut SchemaVisitor) {
+    if f(schema, hint) {
+        walk_schema_children(schema, hint, f);
+    }
+}
+
+fn walk_schema_children(schema: &mut Value, hint: &str, f: &mut SchemaVisitor) {
+    let schema = match schema.as_object_mut() {
+        Some(v) => v,
+        None => return,
+    };
+    for (key, value) in schema.iter_mut() {
+        match key.as_str() {
+            "properties" | "patternProperties" => {
+                if let Some(properties) = value.as_object_mut() {
+                    for (name, value) in properties.iter_mut() {
+                        let mut words = naming::split_words(hint);
+                        words.extend(naming::split_words(name));
+                        walk_schema(value, &naming::to_pascal_case(&words), f);
+                    }
+                }
+            }
+            "items" => match value {
+                Value::Array(items) => {
+                    for (index, value) in items.iter_mut().enumerate() {
+                        walk_schema(value, &format!("{hint}Item{}", index + 1), f);
+                    }
+                }
+                _ => walk_schema(value, &format!("{hint}Item"), f),
+            },
+            "additionalProperties" => walk_schema(value, &format!("{hint}Value"), f),
+            "not" => walk_schema(value, &format!("{hint}Not"), f),
+            "allOf" | "anyOf" | "oneOf" => {
+                if let Some(schemas) = value.as_array_mut() {
+                    for (index, value) in schemas.iter_mut().enumerate() {
+                        walk_schema(value, &format!("{hint}{}", index + 1), f);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -7,4 +7,88 @@ macro_rules! snapshot {
         let output = serde_json::to_string_pretty(&openapi).unwrap();
         insta::assert_snapshot!(output);
     };
+    ($source:literal, $options:expr) => {
+        let input = include_str!($source);
+        let node: jsona::dom::Node = input.parse().unwrap();
+        let openapi = jsona_openapi::Openapi::parse_with_options(&node, &$options).unwrap();
+        let output = serde_json::to_string_pretty(&openapi).unwrap();
+        insta::assert_snapshot!(output);
+    };
+}
+
+#[macro_export]
+macro_rules! snapshot_errors {
+    ($source:literal) => {
+        let input = include_str!($source);
+        let node: jsona::dom::Node = input.parse().unwrap();
+        let errors = jsona_openapi::Openapi::try_from(&node).unwrap_err();
+        let output = errors
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<String>>()
+            .join("\n");
+        insta::assert_snapshot!(output);
+    };
+    ($source:literal, $options:expr) => {
+        let input = include_str!($source);
+        let node: jsona::dom::Node = input.parse().unwrap();
+        let errors = jsona_openapi::Openapi::parse_with_options(&node, &$options).unwrap_err();
+        let output = errors
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<String>>()
+            .join("\n");
+        insta::assert_snapshot!(output);
+    };
+}
+
+#[macro_export]
+macro_rules! snapshot_file {
+    ($path:literal) => {
+        let openapi = jsona_openapi::Openapi::parse_file($path, &Default::default()).unwrap();
+        let output = serde_json::to_string_pretty(&openapi).unwrap();
+        insta::assert_snapshot!(output);
+    };
+    ($path:literal, $options:expr) => {
+        let openapi = jsona_openapi::Openapi::parse_file($path, &$options).unwrap();
+        let output = serde_json::to_string_pretty(&openapi).unwrap();
+        insta::assert_snapshot!(output);
+    };
+}
+
+#[macro_export]
+macro_rules! snapshot_file_errors {
+    ($path:literal) => {
+        let errors = jsona_openapi::Openapi::parse_file($path, &Default::default()).unwrap_err();
+        let output = errors
+            .iter()
+            .map(|v| {
+                format!(
+                    "{} {:?}",
+                    v,
+                    v.location()
+                        .and_then(|v| v.range)
+                        .map(|v| (v.start.line, v.start.column))
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+        insta::assert_snapshot!(output);
+    };
+}
+
+#[macro_export]
+macro_rules! snapshot_warnings {
+    ($source:literal) => {
+        let input = include_str!($source);
+        let node: jsona::dom::Node = input.parse().unwrap();
+        let (_, warnings) =
+            jsona_openapi::Openapi::parse_with_warnings(&node, &Default::default()).unwrap();
+        let output = warnings
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<String>>()
+            .join("\n");
+        insta::assert_snapshot!(output);
+    };
 }
@@ -1,12 +1,263 @@
 #[macro_use]
 mod macros;
 
+use std::path::PathBuf;
+
+use jsona::dom::Node;
+use jsona_openapi::{ExternalRefMode, Openapi, OpenapiOptions, OperationIdStrategy};
+
 #[test]
 fn all_case() {
     snapshot!("fixtures/all_cases.jsona");
 }
 
+#[test]
+fn parameter_style() {
+    snapshot!("fixtures/parameter_style.jsona");
+}
+
 #[test]
 fn petstore() {
     snapshot!("fixtures/petstore.jsona");
 }
+
+#[test]
+fn groups() {
+    snapshot!("fixtures/groups.jsona");
+}
+
+#[test]
+fn paths() {
+    snapshot!("fixtures/paths.jsona");
+}
+
+#[test]
+fn security() {
+    snapshot!("fixtures/security.jsona");
+}
+
+#[test]
+fn security_schemes() {
+    snapshot!("fixtures/security_schemes.jsona");
+}
+
+#[test]
+fn extensions() {
+    snapshot!("fixtures/extensions.jsona");
+}
+
+#[test]
+fn errors() {
+    snapshot_errors!("fixtures/errors.jsona");
+}
+
+#[test]
+fn operation_id_camel_case() {
+    snapshot!(
+        "fixtures/operation_id.jsona",
+        OpenapiOptions {
+            operation_id: OperationIdStrategy::CamelCase,
+            operation_id_tag_prefix: true,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn operation_id_method_path() {
+    snapshot!(
+        "fixtures/operation_id.jsona",
+        OpenapiOptions {
+            operation_id: OperationIdStrategy::MethodPath,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn callbacks() {
+    snapshot!("fixtures/callbacks.jsona");
+}
+
+#[test]
+fn example_files() {
+    snapshot!("fixtures/examples.jsona");
+}
+
+#[test]
+fn example_files_bundled() {
+    snapshot!(
+        "fixtures/examples.jsona",
+        OpenapiOptions {
+            base_dir: Some(PathBuf::from(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/fixtures"
+            ))),
+            bundle_example_files: true,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn include() {
+    snapshot_file!("tests/fixtures/include/main.jsona");
+}
+
+#[test]
+fn include_errors() {
+    snapshot_file_errors!("tests/fixtures/include/errors.jsona");
+}
+
+#[test]
+fn external_refs_keep() {
+    snapshot_file!("tests/fixtures/refs/main.jsona");
+}
+
+#[test]
+fn external_refs_bundle() {
+    snapshot_file!(
+        "tests/fixtures/refs/main.jsona",
+        OpenapiOptions {
+            external_refs: ExternalRefMode::Bundle,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn external_refs_errors() {
+    snapshot_file_errors!("tests/fixtures/refs/errors.jsona");
+}
+
+#[test]
+fn dereference() {
+    let node: Node = include_str!("fixtures/transform.jsona").parse().unwrap();
+    let mut openapi = Openapi::try_from(&node).unwrap();
+    openapi.dereference();
+    insta::assert_snapshot!(serde_json::to_string_pretty(&openapi).unwrap());
+}
+
+#[test]
+fn hoist_schemas() {
+    let node: Node = include_str!("fixtures/transform.jsona").parse().unwrap();
+    let mut openapi = Openapi::try_from(&node).unwrap();
+    openapi.hoist_schemas();
+    insta::assert_snapshot!(serde_json::to_string_pretty(&openapi).unwrap());
+}
+
+#[test]
+fn dedupe_schemas() {
+    snapshot!(
+        "fixtures/dedupe.jsona",
+        OpenapiOptions {
+            dedupe_schemas: true,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn name_inline_schemas() {
+    snapshot!(
+        "fixtures/dedupe.jsona",
+        OpenapiOptions {
+            name_inline_schemas: true,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn infer_formats() {
+    snapshot!(
+        "fixtures/formats.jsona",
+        OpenapiOptions {
+            infer_formats: true,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn enums() {
+    snapshot!("fixtures/enums.jsona");
+}
+
+#[test]
+fn enums_warnings() {
+    snapshot_warnings!("fixtures/enums.jsona");
+}
+
+#[test]
+fn polymorphism() {
+    snapshot!("fixtures/polymorphism.jsona");
+}
+
+#[test]
+fn extends() {
+    snapshot!("fixtures/extends.jsona");
+}
+
+#[test]
+fn extends_flatten() {
+    snapshot!(
+        "fixtures/extends.jsona",
+        OpenapiOptions {
+            flatten_extends: true,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn extends_flatten_errors() {
+    snapshot_errors!(
+        "fixtures/extends_errors.jsona",
+        OpenapiOptions {
+            flatten_extends: true,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn generics() {
+    snapshot!("fixtures/generics.jsona");
+}
+
+#[test]
+fn read_write() {
+    snapshot!("fixtures/readwrite.jsona");
+}
+
+#[test]
+fn read_write_split() {
+    snapshot!(
+        "fixtures/readwrite.jsona",
+        OpenapiOptions {
+            split_read_write: true,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn nullable() {
+    snapshot_file!("tests/fixtures/nullable/main.jsona");
+}
+
+#[test]
+fn nullable_v3_1() {
+    snapshot_file!("tests/fixtures/nullable/v3_1.jsona");
+}
+
+#[test]
+fn warnings() {
+    snapshot_warnings!("fixtures/warnings.jsona");
+}
+
+#[test]
+fn responses() {
+    snapshot!("fixtures/responses.jsona");
+}